@@ -0,0 +1,420 @@
+use crate::error::SyntaxError;
+use crate::refs::{Context, Extractor, Reference, ReferenceKind};
+use crate::structure::{join_path, normalize_path, parent_path};
+
+use serde_json::Value;
+
+/// Finds the `#include` directives in C and C++ source and header files. `#include "a.h"`
+/// is looked for next to the including file and then in the include folders,
+/// `#include <a.h>` only in the include folders. The include folders are the configured
+/// ones followed by the `-I`, `-iquote` and `-isystem` folders of all commands in the
+/// compilation database, when there is one.
+#[derive(Debug, Clone)]
+pub struct CExtractor {
+    /// Folders to look for included files in, relative to the scanned folder
+    pub include_dirs: Vec<String>,
+    /// The path of the `compile_commands.json` to read more include folders from, relative
+    /// to the scanned folder
+    pub compile_commands: Option<String>,
+}
+
+impl Default for CExtractor {
+    fn default() -> Self {
+        CExtractor {
+            include_dirs: Vec::new(),
+            compile_commands: Some("compile_commands.json".to_string()),
+        }
+    }
+}
+
+impl Extractor for CExtractor {
+    fn language(&self) -> &str {
+        "c"
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        let extension = path.rsplit_once('.').map_or("", |(_, e)| e);
+        matches!(
+            extension,
+            "c" | "h" | "cc" | "cpp" | "cxx" | "c++" | "hh" | "hpp" | "hxx" | "h++" | "inl" | "ipp"
+        )
+    }
+
+    fn extract(&self, path: &str, contents: &str) -> Result<Vec<Reference>, SyntaxError> {
+        let folder = parent_path(path);
+        let mut refs = Vec::new();
+        let mut comment = false;
+        for line in contents.lines() {
+            let line = strip_comments(line, &mut comment);
+            let rest = match line.trim_start().strip_prefix('#') {
+                Some(rest) => rest.trim_start(),
+                None => continue,
+            };
+            let rest = match rest.strip_prefix("include") {
+                Some(rest) => rest.trim_start(),
+                None => continue,
+            };
+            // Quoted includes are relative to the file, angled ones only to the include folders
+            let (kind, rest, end) = if let Some(rest) = rest.strip_prefix('"') {
+                (ReferenceKind::Include, rest, '"')
+            } else if let Some(rest) = rest.strip_prefix('<') {
+                (ReferenceKind::Import, rest, '>')
+            } else {
+                continue;
+            };
+            if let Some((target, _)) = rest.split_once(end) {
+                refs.push(Reference::new(kind, target, folder));
+            }
+        }
+        Ok(refs)
+    }
+
+    fn resolve(&self, path: &str, reference: &Reference, context: &Context) -> Option<String> {
+        let exists = |p: &str| context.exists(p);
+        // The include folders of the compilation database relative to the scanned folder,
+        // known once the scanned folder is found in the absolute paths of the database
+        let database = self.compile_commands.as_deref().and_then(|commands| {
+            context.parse(commands, |text| {
+                let json = serde_json::from_str::<Value>(text).ok()?;
+                Some(parse_compile_commands(&json).relative_include_dirs(&exists))
+            })
+        });
+        let include_dirs = self
+            .include_dirs
+            .iter()
+            .map(|d| normalize_path(d))
+            .chain(database.iter().flat_map(|dirs| dirs.iter().cloned()));
+        resolve(path, reference, include_dirs, &exists)
+    }
+
+    fn reads(&self, path: &str) -> bool {
+        self.compile_commands.as_deref() == Some(path)
+    }
+}
+
+/// Remove the comments from a line, `comment` tells whether the line starts inside a block
+/// comment and is updated for the next line
+fn strip_comments(line: &str, comment: &mut bool) -> String {
+    let mut text = String::new();
+    let mut rest = line;
+    loop {
+        if *comment {
+            match rest.split_once("*/") {
+                Some((_, after)) => {
+                    *comment = false;
+                    rest = after;
+                }
+                None => return text,
+            }
+        }
+        let block = rest.find("/*");
+        match rest.find("//") {
+            Some(start) if block.is_none_or(|b| start < b) => {
+                text.push_str(&rest[..start]);
+                return text;
+            }
+            _ => {}
+        }
+        match block {
+            Some(start) => {
+                text.push_str(&rest[..start]);
+                // A comment counts as whitespace
+                text.push(' ');
+                *comment = true;
+                rest = &rest[start + 2..];
+            }
+            None => {
+                text.push_str(rest);
+                return text;
+            }
+        }
+    }
+}
+
+fn resolve(
+    path: &str,
+    reference: &Reference,
+    include_dirs: impl Iterator<Item = String>,
+    exists: &dyn Fn(&str) -> bool,
+) -> Option<String> {
+    if reference.kind == ReferenceKind::Include {
+        let found = join_path(parent_path(path), &reference.target);
+        if exists(&found) {
+            return Some(found);
+        }
+    }
+    include_dirs
+        .map(|dir| join_path(&dir, &reference.target))
+        .find(|p| exists(p))
+}
+
+/// The files and include folders in a compilation database, as absolute paths without the
+/// leading `/`
+#[derive(Debug, Default)]
+struct CompileCommands {
+    files: Vec<String>,
+    include_dirs: Vec<String>,
+}
+
+impl CompileCommands {
+    /// Get the include folders relative to the scanned folder, by looking for the files of
+    /// the database in it. Folders outside of the scanned folder are left out.
+    fn relative_include_dirs(&self, exists: &dyn Fn(&str) -> bool) -> Vec<String> {
+        let root = self.files.iter().find_map(|file| {
+            let mut suffix = file.as_str();
+            loop {
+                if exists(suffix) {
+                    return Some(&file[..file.len() - suffix.len()]);
+                }
+                suffix = suffix.split_once('/')?.1;
+            }
+        });
+        let root = match root {
+            Some(root) => root.trim_end_matches('/'),
+            None => return Vec::new(),
+        };
+        self.include_dirs
+            .iter()
+            .filter_map(|dir| {
+                if root.is_empty() {
+                    Some(dir.clone())
+                } else if dir == root {
+                    Some(String::new())
+                } else {
+                    dir.strip_prefix(root)?
+                        .strip_prefix('/')
+                        .map(str::to_string)
+                }
+            })
+            .collect()
+    }
+}
+
+/// Read the files and include folders from the entries of a `compile_commands.json`
+fn parse_compile_commands(json: &Value) -> CompileCommands {
+    let mut commands = CompileCommands::default();
+    for entry in json.as_array().into_iter().flatten() {
+        let directory = entry
+            .get("directory")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let absolute = |path: &str| {
+            if path.starts_with('/') || path.get(1..2) == Some(":") {
+                normalize_path(path)
+            } else {
+                join_path(&normalize_path(directory), path)
+            }
+        };
+        if let Some(file) = entry.get("file").and_then(Value::as_str) {
+            commands.files.push(absolute(file));
+        }
+        let arguments = match entry.get("arguments").and_then(Value::as_array) {
+            Some(arguments) => arguments
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect(),
+            None => entry
+                .get("command")
+                .and_then(Value::as_str)
+                .map(|c| {
+                    c.split_whitespace()
+                        .map(|a| a.trim_matches('"').to_string())
+                        .collect()
+                })
+                .unwrap_or_else(Vec::new),
+        };
+        let mut arguments = arguments.iter();
+        while let Some(argument) = arguments.next() {
+            let dir = ["-I", "-iquote", "-isystem", "/I"]
+                .iter()
+                .find_map(|flag| argument.strip_prefix(flag))
+                .map(|dir| match dir {
+                    "" => arguments.next().map(String::as_str),
+                    dir => Some(dir),
+                });
+            if let Some(Some(dir)) = dir {
+                let dir = absolute(dir);
+                if !commands.include_dirs.contains(&dir) {
+                    commands.include_dirs.push(dir);
+                }
+            }
+        }
+    }
+    commands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn targets(contents: &str) -> Vec<(ReferenceKind, String)> {
+        CExtractor::default()
+            .extract("src/a.c", contents)
+            .unwrap()
+            .into_iter()
+            .map(|r| (r.kind, r.target))
+            .collect()
+    }
+
+    /// Resolve an include in a folder with the given files and database
+    fn resolve(
+        extractor: &CExtractor,
+        path: &str,
+        target: &str,
+        files: &[&str],
+        database: Option<Value>,
+    ) -> Option<String> {
+        let exists = |p: &str| files.contains(&p);
+        let read = |_: &str| database.as_ref().map(Value::to_string);
+        let kind = if target.starts_with('<') {
+            ReferenceKind::Import
+        } else {
+            ReferenceKind::Include
+        };
+        let target = target.trim_matches(['<', '>']);
+        let reference = Reference::new(kind, target, parent_path(path));
+        extractor.resolve(path, &reference, &Context::new(&exists, &read))
+    }
+
+    #[test]
+    fn includes() {
+        let source = concat!(
+            "#include \"a.h\"\n",
+            "  #  include <sys/b.h> // a comment\n",
+            "#define X \"c.h\"\n",
+            "#include X\n",
+        );
+        assert_eq!(
+            targets(source),
+            vec![
+                (ReferenceKind::Include, "a.h".to_string()),
+                (ReferenceKind::Import, "sys/b.h".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn comments() {
+        let source = concat!(
+            "/* #include \"a.h\"\n",
+            "#include \"b.h\" */ #include \"c.h\"\n",
+            "/* one */ #include \"d.h\"\n",
+            "int x; /* starts here\n",
+            "#include \"e.h\"\n",
+            "*/\n",
+            "// #include \"f.h\" /*\n",
+            "#include \"g.h\"\n",
+        );
+        let targets = targets(source)
+            .into_iter()
+            .map(|(_, target)| target)
+            .collect::<Vec<_>>();
+        // A comment is a space, so `c.h` starts the line the comment starts on
+        assert_eq!(targets, vec!["c.h", "d.h", "g.h"]);
+    }
+
+    #[test]
+    fn include_folders() {
+        let files = ["src/a.h", "include/a.h", "include/b.h"];
+        let extractor = CExtractor {
+            include_dirs: vec!["./include/".to_string()],
+            compile_commands: None,
+        };
+        // Quoted includes are looked for next to the file first
+        assert_eq!(
+            resolve(&extractor, "src/a.c", "a.h", &files, None),
+            Some("src/a.h".to_string())
+        );
+        assert_eq!(
+            resolve(&extractor, "src/a.c", "<a.h>", &files, None),
+            Some("include/a.h".to_string())
+        );
+        assert_eq!(
+            resolve(&extractor, "src/a.c", "b.h", &files, None),
+            Some("include/b.h".to_string())
+        );
+        assert_eq!(
+            resolve(&extractor, "src/a.c", "<stdio.h>", &files, None),
+            None
+        );
+    }
+
+    #[test]
+    fn compile_command_flags() {
+        let database = json!([
+            {
+                "directory": "/home/me/project/build",
+                "file": "../src/a.c",
+                "arguments": ["cc", "-I", "../include", "-I../gen", "-iquote", "q", "-isystem/usr/include", "-DX", "-c", "../src/a.c"]
+            },
+            {
+                "directory": "C:\\project",
+                "file": "src\\b.c",
+                "command": "cl.exe /Iwin \"-Iquoted\" -I../include /c src\\b.c"
+            }
+        ]);
+        let commands = parse_compile_commands(&database);
+        assert_eq!(
+            commands.files,
+            vec!["home/me/project/src/a.c", "C:/project/src/b.c"]
+        );
+        assert_eq!(
+            commands.include_dirs,
+            vec![
+                "home/me/project/include",
+                "home/me/project/gen",
+                "home/me/project/build/q",
+                "usr/include",
+                "C:/project/win",
+                "C:/project/quoted",
+                "C:/include",
+            ]
+        );
+    }
+
+    #[test]
+    fn compile_commands_relative_to_the_scanned_folder() {
+        let commands = CompileCommands {
+            files: vec!["home/me/project/src/a.c".to_string()],
+            include_dirs: vec![
+                "home/me/project/include".to_string(),
+                "home/me/project".to_string(),
+                "usr/include".to_string(),
+            ],
+        };
+        // The scanned folder is the project
+        assert_eq!(
+            commands.relative_include_dirs(&|p| p == "src/a.c"),
+            vec!["include", ""]
+        );
+        // The scanned folder is the parent of the project
+        assert_eq!(
+            commands.relative_include_dirs(&|p| p == "project/src/a.c"),
+            vec!["project/include", "project"]
+        );
+        // None of the files are in the scanned folder
+        assert!(commands.relative_include_dirs(&|_| false).is_empty());
+    }
+
+    #[test]
+    fn compile_commands_are_read() {
+        let database = json!([{
+            "directory": "/work/project",
+            "file": "src/a.c",
+            "arguments": ["cc", "-Ilib/include", "src/a.c"]
+        }]);
+        let files = ["src/a.c", "lib/include/z.h"];
+        assert_eq!(
+            resolve(
+                &CExtractor::default(),
+                "src/a.c",
+                "<z.h>",
+                &files,
+                Some(database)
+            ),
+            Some("lib/include/z.h".to_string())
+        );
+    }
+}
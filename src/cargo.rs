@@ -0,0 +1,168 @@
+use crate::error::*;
+use crate::structure::{join_path, normalize_path};
+
+use globset::Glob;
+use std::fs;
+use std::path::Path;
+use toml::{Table, Value};
+
+/// A crate in a Cargo workspace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Package {
+    /// The name of the crate
+    pub name: String,
+    /// The folder of the crate relative to the scanned folder, empty for the root
+    pub path: String,
+    /// The names of the other crates in the workspace this crate depends on, by path or
+    /// through `workspace = true`
+    pub dependencies: Vec<String>,
+}
+
+/// The crates of a Cargo workspace
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Workspace {
+    pub packages: Vec<Package>,
+}
+
+/// The tables that list dependencies in a manifest, also found below `[target.'cfg(..)']`
+const DEPENDENCY_TABLES: [&str; 3] = ["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// Read the `Cargo.toml` in the given folder and the manifests of all workspace members it
+/// lists. A folder without a `Cargo.toml` gives an empty workspace.
+pub fn get_workspace(path: &Path) -> Result<Workspace> {
+    let root = match read_manifest(&path.join("Cargo.toml"))? {
+        Some(root) => root,
+        None => return Ok(Workspace::default()),
+    };
+    let workspace = root.get("workspace").and_then(Value::as_table);
+    let strings = |key: &str| {
+        workspace
+            .and_then(|w| w.get(key))
+            .and_then(Value::as_array)
+            .map(|a| a.iter().filter_map(Value::as_str).collect::<Vec<_>>())
+            .unwrap_or_default()
+    };
+    let excluded = strings("exclude")
+        .into_iter()
+        .map(normalize_path)
+        .collect::<Vec<_>>();
+    let mut members = vec![String::new()];
+    for pattern in strings("members") {
+        for member in expand_member(path, pattern) {
+            if !members.contains(&member) && !excluded.contains(&member) {
+                members.push(member);
+            }
+        }
+    }
+    // Dependencies inherited with `workspace = true`, by name
+    let shared = workspace
+        .and_then(|w| w.get("dependencies"))
+        .and_then(Value::as_table);
+
+    let mut manifests = Vec::new();
+    for member in members {
+        let manifest = if member.is_empty() {
+            Some(root.clone())
+        } else {
+            read_manifest(&path.join(&member).join("Cargo.toml"))?
+        };
+        let name = manifest
+            .as_ref()
+            .and_then(|m| m.get("package"))
+            .and_then(|p| p.get("name"))
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        if let (Some(manifest), Some(name)) = (manifest, name) {
+            manifests.push((member, name, manifest));
+        }
+    }
+
+    let packages = manifests
+        .iter()
+        .map(|(member, name, manifest)| {
+            let mut dependencies = Vec::new();
+            for (key, dependency) in dependency_tables(manifest).flat_map(|t| t.iter()) {
+                let target = match dependency.get("path").and_then(Value::as_str) {
+                    Some(dependency_path) => join_path(member, dependency_path),
+                    None if dependency.get("workspace").and_then(Value::as_bool) == Some(true) => {
+                        match shared
+                            .and_then(|s| s.get(key))
+                            .and_then(|d| d.get("path"))
+                            .and_then(Value::as_str)
+                        {
+                            Some(dependency_path) => normalize_path(dependency_path),
+                            None => continue,
+                        }
+                    }
+                    None => continue,
+                };
+                if let Some((_, other, _)) = manifests.iter().find(|(m, ..)| *m == target) {
+                    if other != name && !dependencies.contains(other) {
+                        dependencies.push(other.clone());
+                    }
+                }
+            }
+            Package {
+                name: name.clone(),
+                path: member.clone(),
+                dependencies,
+            }
+        })
+        .collect();
+    Ok(Workspace { packages })
+}
+
+/// Read and parse a manifest, `None` if it does not exist
+fn read_manifest(path: &Path) -> Result<Option<Table>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(Error::io(path, e)),
+    };
+    text.parse::<Table>()
+        .map(Some)
+        .map_err(|e| Error::InvalidManifest {
+            path: path.to_path_buf(),
+            message: e.message().to_string(),
+        })
+}
+
+/// All dependency tables of a manifest, including the ones for specific targets
+fn dependency_tables(manifest: &Table) -> impl Iterator<Item = &Table> {
+    let targets = manifest
+        .get("target")
+        .and_then(Value::as_table)
+        .into_iter()
+        .flat_map(|t| t.values())
+        .filter_map(Value::as_table);
+    std::iter::once(manifest)
+        .chain(targets)
+        .flat_map(|t| DEPENDENCY_TABLES.iter().filter_map(|key| t.get(*key)))
+        .filter_map(Value::as_table)
+}
+
+/// Find the folders matching a workspace member pattern like `crates/*`
+fn expand_member(root: &Path, pattern: &str) -> Vec<String> {
+    let mut folders = vec![String::new()];
+    for component in normalize_path(pattern).split('/') {
+        let matcher = match Glob::new(component) {
+            Ok(glob) => glob.compile_matcher(),
+            Err(_) => return Vec::new(),
+        };
+        folders = folders
+            .iter()
+            .flat_map(|folder| {
+                let names = fs::read_dir(root.join(folder))
+                    .into_iter()
+                    .flatten()
+                    .flatten()
+                    .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+                    .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+                    .filter(|name| matcher.is_match(name))
+                    .collect::<Vec<_>>();
+                names.into_iter().map(move |name| join_path(folder, &name))
+            })
+            .collect();
+    }
+    folders
+}
@@ -0,0 +1,197 @@
+use crate::history::format_date;
+use crate::structure::{FileType, Item};
+
+use std::collections::HashMap;
+
+/// What the colour of a file shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColourMode {
+    /// The kind of file, see [`crate::FileType`]
+    #[default]
+    Type,
+    /// The number of commits that changed the file, on a heat scale
+    Commits,
+    /// The number of lines added and removed over the history of the file, on a heat scale
+    Churn,
+    /// The time the file was last changed, from old (cold) to recent (hot)
+    Age,
+    /// The time the file was added, from old (cold) to recent (hot)
+    Created,
+    /// The author with the most commits to the file
+    Author,
+    /// The number of commits times the number of lines of the file, on a heat scale
+    Hotspot,
+}
+
+impl ColourMode {
+    /// Check if the git history has to be added to the structure for this mode
+    pub fn needs_history(&self) -> bool {
+        !matches!(self, ColourMode::Type)
+    }
+
+    /// Check if the values of this mode are shown on a logarithmic scale, because a few
+    /// files tend to have far higher values than all others
+    fn is_logarithmic(&self) -> bool {
+        matches!(
+            self,
+            ColourMode::Commits | ColourMode::Churn | ColourMode::Hotspot
+        )
+    }
+}
+
+/// The colours given to the authors with the most files, in order
+const AUTHOR_COLOURS: [&str; 10] = [
+    "#4E79A7", "#F28E2B", "#E15759", "#76B7B2", "#59A14F", "#EDC948", "#B07AA1", "#FF9DA7",
+    "#9C755F", "#BAB0AC",
+];
+/// The colour for files without a (known) value
+const NO_VALUE_COLOUR: &str = "var(--color-light)";
+
+/// What the size of a file shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SizeMode {
+    /// The size of the file in bytes
+    #[default]
+    Bytes,
+    /// The number of lines added and removed over the history of the file
+    Churn,
+}
+
+impl SizeMode {
+    /// Check if the git history has to be added to the structure for this mode
+    pub fn needs_history(&self) -> bool {
+        !matches!(self, SizeMode::Bytes)
+    }
+}
+
+/// The colour mode together with the range of the values shown in a plot
+#[derive(Debug, Clone)]
+pub struct ColourScheme {
+    pub mode: ColourMode,
+    /// The lowest value of any file, the bottom of the heat scale
+    min: f64,
+    /// The highest value of any file, the top of the heat scale
+    max: f64,
+    /// The authors that get their own colour, the ones that are the primary author of the
+    /// most files first
+    authors: Vec<String>,
+}
+
+impl ColourScheme {
+    /// Create the scheme for the given files
+    pub fn new<'a>(mode: ColourMode, files: impl IntoIterator<Item = &'a Item>) -> Self {
+        let files = files.into_iter().collect::<Vec<_>>();
+        let (min, max) = files
+            .iter()
+            .filter_map(|f| f.metric(mode))
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+                (min.min(v), max.max(v))
+            });
+        let (min, max) = if mode.is_logarithmic() {
+            (0.0, max.max(0.0))
+        } else {
+            (min, max)
+        };
+
+        let mut owned_files: HashMap<&str, usize> = HashMap::new();
+        for author in files.iter().filter_map(|f| f.primary_author()) {
+            *owned_files.entry(author).or_default() += 1;
+        }
+        let mut authors = owned_files.into_iter().collect::<Vec<_>>();
+        authors.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        let authors = authors
+            .into_iter()
+            .take(AUTHOR_COLOURS.len())
+            .map(|(name, _)| name.to_string())
+            .collect();
+
+        ColourScheme {
+            mode,
+            min,
+            max,
+            authors,
+        }
+    }
+
+    /// Get the colour for the given file
+    pub fn colour(&self, item: &Item) -> String {
+        match self.mode {
+            ColourMode::Type => match item {
+                Item::File { class, .. } => class.colour().to_string(),
+                Item::Folder { .. } => NO_VALUE_COLOUR.to_string(),
+            },
+            ColourMode::Author => item
+                .primary_author()
+                .and_then(|author| self.authors.iter().position(|a| a == author))
+                .map_or(NO_VALUE_COLOUR, |index| AUTHOR_COLOURS[index])
+                .to_string(),
+            mode => match item.metric(mode) {
+                Some(value) => self.heat(value),
+                None => NO_VALUE_COLOUR.to_string(),
+            },
+        }
+    }
+
+    /// Get the labels and colours to explain this scheme
+    pub fn legend(&self) -> Vec<(String, String)> {
+        match self.mode {
+            ColourMode::Type => [
+                FileType::Code,
+                FileType::Data,
+                FileType::Configuration,
+                FileType::Unknown,
+            ]
+            .iter()
+            .map(|t| (t.name().to_string(), t.colour().to_string()))
+            .collect(),
+            ColourMode::Author => {
+                let mut legend = self
+                    .authors
+                    .iter()
+                    .zip(AUTHOR_COLOURS)
+                    .map(|(author, colour)| (author.to_string(), colour.to_string()))
+                    .collect::<Vec<_>>();
+                legend.push(("Others".to_string(), NO_VALUE_COLOUR.to_string()));
+                legend
+            }
+            _ if self.max < self.min => {
+                vec![("No history".to_string(), NO_VALUE_COLOUR.to_string())]
+            }
+            mode => (0..5)
+                .rev()
+                .map(|step| {
+                    let fraction = step as f64 / 4.0;
+                    let value = if mode.is_logarithmic() {
+                        (fraction * self.max.ln_1p()).exp_m1()
+                    } else {
+                        self.min + fraction * (self.max - self.min)
+                    };
+                    let label = match mode {
+                        ColourMode::Age | ColourMode::Created => format_date(value as i64),
+                        _ => format!("{:.0}", value),
+                    };
+                    (label, heat(fraction))
+                })
+                .collect(),
+        }
+    }
+
+    /// Get the colour on the heat scale for the given value
+    pub fn heat(&self, value: f64) -> String {
+        if self.max <= self.min {
+            heat(1.0)
+        } else if self.mode.is_logarithmic() {
+            heat(value.ln_1p() / self.max.ln_1p())
+        } else {
+            heat((value - self.min) / (self.max - self.min))
+        }
+    }
+}
+
+/// A colour on a heat scale from cold (blue, 0.0) to hot (red, 1.0)
+pub fn heat(fraction: f64) -> String {
+    format!(
+        "hsl({:.0}, 80%, 50%)",
+        240.0 * (1.0 - fraction.clamp(0.0, 1.0))
+    )
+}
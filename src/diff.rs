@@ -0,0 +1,150 @@
+use crate::error::*;
+use crate::filter::PathFilter;
+use crate::git;
+use crate::structure::*;
+
+use std::collections::HashMap;
+use std::path::Path;
+
+/// How a file changed between two revisions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// The file is new in the newer revision
+    Added,
+    /// The file is removed in the newer revision, it is shown as a ghost
+    Deleted,
+    /// The contents of the file changed, the size changed with the given number of bytes
+    Modified { size_delta: i64 },
+    /// The file was moved from the given path, and its size changed with the given number of bytes
+    Renamed { from: String, size_delta: i64 },
+}
+
+impl Change {
+    /// The name of the kind of change, also used as class in the plot
+    pub fn name(&self) -> &'static str {
+        match self {
+            Change::Added => "added",
+            Change::Deleted => "deleted",
+            Change::Modified { .. } => "modified",
+            Change::Renamed { .. } => "renamed",
+        }
+    }
+
+    /// The labels and colours of all kinds of changes to explain them in a legend
+    pub fn legend() -> Vec<(String, String)> {
+        ["added", "deleted", "modified", "renamed"]
+            .iter()
+            .map(|name| (name.to_string(), format!("var(--color-{})", name)))
+            .collect()
+    }
+}
+
+/// Get the two revisions from a range like `main..feature`, `main...feature` (compared to
+/// the common ancestor of both) or `v1.0` (compared to `HEAD`). An empty side means `HEAD`.
+pub fn parse_range(path: &Path, range: &str) -> Result<(String, String)> {
+    let or_head = |s: &str| {
+        if s.is_empty() {
+            "HEAD".to_string()
+        } else {
+            s.to_string()
+        }
+    };
+    if let Some((old, new)) = range.split_once("...") {
+        let (old, new) = (or_head(old), or_head(new));
+        let base = git::run(path, &["merge-base", "--end-of-options", &old, &new])?;
+        Ok((String::from_utf8_lossy(&base).trim().to_string(), new))
+    } else if let Some((old, new)) = range.split_once("..") {
+        Ok((or_head(old), or_head(new)))
+    } else {
+        Ok((range.to_string(), "HEAD".to_string()))
+    }
+}
+
+/// Scan the files of the newer revision and mark every file that was added, modified or
+/// renamed since the older revision. Files that were deleted are added as ghosts with
+/// the size they had in the older revision.
+pub fn get_diff_structure(
+    path: &Path,
+    old: &str,
+    new: &str,
+    options: &ScanOptions,
+) -> Result<Scan> {
+    let mut scan = get_revision_structure(path, new, options)?;
+    let path = &path.canonicalize().map_err(|e| Error::io(path, e))?;
+    let old_sizes = git::list_tree(path, old)?
+        .into_iter()
+        .map(|entry| (entry.path, entry.size))
+        .collect::<HashMap<_, _>>();
+    let new_sizes = git::list_tree(path, new)?
+        .into_iter()
+        .map(|entry| (entry.path, entry.size))
+        .collect::<HashMap<_, _>>();
+    let size_delta = |old_path: &str, new_path: &str| {
+        new_sizes.get(new_path).copied().unwrap_or(0) as i64
+            - old_sizes.get(old_path).copied().unwrap_or(0) as i64
+    };
+    let ignore = PathFilter::new(&options.ignore)?;
+    let include = PathFilter::new(&options.include)?;
+
+    let output = git::run(
+        path,
+        &[
+            "diff",
+            "--name-status",
+            "-M",
+            "-z",
+            "--relative",
+            "--end-of-options",
+            old,
+            new,
+            "--",
+            ".",
+        ],
+    )?;
+    let output = String::from_utf8_lossy(&output);
+    let mut fields = output.split('\0').filter(|f| !f.is_empty());
+    let mut changes = HashMap::new();
+    while let Some(status) = fields.next() {
+        let first = match fields.next() {
+            Some(f) => f,
+            None => break,
+        };
+        match status.chars().next() {
+            Some('R') => {
+                let to = fields.next().unwrap_or_default();
+                changes.insert(
+                    to.to_string(),
+                    Change::Renamed {
+                        from: first.to_string(),
+                        size_delta: size_delta(first, to),
+                    },
+                );
+            }
+            Some('C') => {
+                let to = fields.next().unwrap_or_default();
+                changes.insert(to.to_string(), Change::Added);
+            }
+            Some('A') => {
+                changes.insert(first.to_string(), Change::Added);
+            }
+            Some('D') => {
+                if is_shown(first, &ignore, &include) {
+                    let size = old_sizes.get(first).copied().unwrap_or(0);
+                    if let Some(file) = scan.root.insert_file(first, size, Vec::new()) {
+                        file.set_change(Change::Deleted);
+                    }
+                }
+            }
+            _ => {
+                changes.insert(
+                    first.to_string(),
+                    Change::Modified {
+                        size_delta: size_delta(first, first),
+                    },
+                );
+            }
+        }
+    }
+    scan.root.add_changes(&mut changes);
+    Ok(scan)
+}
@@ -0,0 +1,176 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A result with the crate level [`Error`]
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// An error that stopped scanning or plotting a repository
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// Reading or writing the given path failed
+    Io { path: PathBuf, source: io::Error },
+    /// The given path is not a file or folder that can be scanned
+    NotScannable(PathBuf),
+    /// The given ignore or include pattern is not a valid glob
+    InvalidPattern { pattern: String, message: String },
+    /// Running the given git command failed
+    Git { command: String, message: String },
+    /// The given manifest (like `Cargo.toml`) could not be parsed
+    InvalidManifest { path: PathBuf, message: String },
+    /// No commit in the history touches the given folder
+    NoCommits(PathBuf),
+}
+
+impl Error {
+    pub(crate) fn io(path: &Path, source: io::Error) -> Self {
+        Error::Io {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    pub(crate) fn git(args: &[&str], message: String) -> Self {
+        Error::Git {
+            command: format!("git {}", args.join(" ")),
+            message,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "'{}': {}", path.display(), source),
+            Error::NotScannable(path) => {
+                write!(f, "'{}' is not a file or folder", path.display())
+            }
+            Error::InvalidPattern { pattern, message } => {
+                write!(f, "invalid pattern '{}': {}", pattern, message)
+            }
+            Error::Git { command, message } => write!(f, "'{}' failed: {}", command, message),
+            Error::InvalidManifest { path, message } => {
+                write!(f, "invalid manifest '{}': {}", path.display(), message)
+            }
+            Error::NoCommits(path) => write!(f, "no commits touch '{}'", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::NotScannable(_)
+            | Error::InvalidPattern { .. }
+            | Error::Git { .. }
+            | Error::InvalidManifest { .. }
+            | Error::NoCommits(_) => None,
+        }
+    }
+}
+
+/// A problem with a single path that was skipped or only partially scanned
+#[derive(Debug, Clone)]
+pub struct Warning {
+    /// The path the problem occurred on
+    pub path: PathBuf,
+    /// What went wrong
+    pub kind: WarningKind,
+}
+
+/// The kind of problem a [`Warning`] reports
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WarningKind {
+    /// The path could not be read, it is left out or shown without references
+    Unreadable(String),
+    /// The file is not valid UTF-8 so no references could be found in it
+    NotUtf8,
+    /// The name of the path is not valid UTF-8, it is left out
+    InvalidName,
+    /// The path is not a file or folder (for example a broken symbolic link), it is left out
+    Unsupported,
+    /// The ignore patterns in the given file could not (all) be used
+    InvalidPattern(String),
+    /// The path is changed by a patch but is not in the scanned files
+    NotFound,
+    /// The file could not be parsed so no references could be found in it
+    InvalidSyntax(SyntaxError),
+}
+
+/// Why an [`Extractor`](crate::Extractor) could not parse a source file
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SyntaxError {
+    /// A description of the problem
+    pub message: String,
+}
+
+impl SyntaxError {
+    pub fn new(message: impl Into<String>) -> Self {
+        SyntaxError {
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for SyntaxError {}
+
+impl Warning {
+    pub(crate) fn new(path: &Path, kind: WarningKind) -> Self {
+        Warning {
+            path: path.to_path_buf(),
+            kind,
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            WarningKind::Unreadable(reason) => {
+                write!(f, "could not read '{}': {}", self.path.display(), reason)
+            }
+            WarningKind::NotUtf8 => write!(
+                f,
+                "'{}' is not valid UTF-8, no references are shown",
+                self.path.display()
+            ),
+            WarningKind::InvalidName => write!(
+                f,
+                "the name of '{}' is not valid UTF-8, it is left out",
+                self.path.display()
+            ),
+            WarningKind::Unsupported => write!(
+                f,
+                "'{}' is not a file or folder, it is left out",
+                self.path.display()
+            ),
+            WarningKind::InvalidPattern(reason) => write!(
+                f,
+                "invalid ignore pattern in '{}': {}",
+                self.path.display(),
+                reason
+            ),
+            WarningKind::InvalidSyntax(reason) => write!(
+                f,
+                "could not parse '{}' ({}), no references are shown",
+                self.path.display(),
+                reason
+            ),
+            WarningKind::NotFound => write!(
+                f,
+                "'{}' is changed by the patch but is not in the scanned files",
+                self.path.display()
+            ),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use crate::error::*;
+
+use globset::{GlobBuilder, GlobMatcher};
+
+/// An ordered list of glob patterns matched against repository relative paths (using `/`
+/// as separator). Patterns starting with `!` are negated and later patterns override
+/// earlier ones. Patterns without a `/` match at any depth, so `target` matches every
+/// folder named target and `*.min.js` every minified file. Other patterns are anchored
+/// at the root, like `docs/generated/**`.
+#[derive(Debug, Clone, Default)]
+pub struct PathFilter {
+    patterns: Vec<(GlobMatcher, bool)>,
+}
+
+impl PathFilter {
+    /// Compile the given patterns
+    pub fn new<S: AsRef<str>>(patterns: &[S]) -> Result<Self> {
+        let mut compiled = Vec::with_capacity(patterns.len());
+        for pattern in patterns {
+            let pattern = pattern.as_ref();
+            let (glob, positive) = match pattern.strip_prefix('!') {
+                Some(p) => (p, false),
+                None => (pattern, true),
+            };
+            let glob = glob.trim_end_matches('/');
+            let glob = if let Some(anchored) = glob.strip_prefix('/') {
+                anchored.to_string()
+            } else if glob.contains('/') {
+                glob.to_string()
+            } else {
+                format!("**/{}", glob)
+            };
+            let matcher = GlobBuilder::new(&glob)
+                .literal_separator(true)
+                .build()
+                .map_err(|e| Error::InvalidPattern {
+                    pattern: pattern.to_string(),
+                    message: e.kind().to_string(),
+                })?
+                .compile_matcher();
+            compiled.push((matcher, positive));
+        }
+        Ok(PathFilter { patterns: compiled })
+    }
+
+    /// Check if there are no patterns
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Check if there is at least one pattern that is not negated
+    pub fn has_positive(&self) -> bool {
+        self.patterns.iter().any(|(_, positive)| *positive)
+    }
+
+    /// Get the result of the last pattern that matches the path, `Some(true)` for a
+    /// normal pattern, `Some(false)` for a negated one and `None` if no pattern matches.
+    pub fn matched(&self, path: &str) -> Option<bool> {
+        self.patterns
+            .iter()
+            .rev()
+            .find(|(matcher, _)| matcher.is_match(path))
+            .map(|(_, positive)| *positive)
+    }
+
+    /// Get the result of the last pattern that matches the path or any of its parent
+    /// folders, the path itself takes precedence over its parents.
+    pub fn matched_path_or_parents(&self, path: &str) -> Option<bool> {
+        let mut current = path;
+        loop {
+            if let Some(result) = self.matched(current) {
+                return Some(result);
+            }
+            match current.rfind('/') {
+                Some(index) => current = &current[..index],
+                None => return None,
+            }
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use crate::error::*;
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+
+/// Run git with the given arguments in the given folder and get its standard output. Put
+/// `--end-of-options` before revisions that come from users, so they are never read as
+/// options.
+pub(crate) fn run(folder: &Path, args: &[&str]) -> Result<Vec<u8>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(folder)
+        .args(args)
+        .output()
+        .map_err(|e| Error::git(args, e.to_string()))?;
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(Error::git(
+            args,
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ))
+    }
+}
+
+/// A file in a git tree
+#[derive(Debug, Clone)]
+pub(crate) struct TreeEntry {
+    /// The path relative to the folder the tree was listed in, using `/` as separator
+    pub path: String,
+    /// The id of the blob with the contents
+    pub id: String,
+    /// The size in bytes
+    pub size: u64,
+}
+
+/// List all files in the given revision below the given folder, submodules are left out
+pub(crate) fn list_tree(folder: &Path, revision: &str) -> Result<Vec<TreeEntry>> {
+    let output = run(
+        folder,
+        &[
+            "ls-tree",
+            "-r",
+            "-l",
+            "-z",
+            "--end-of-options",
+            revision,
+            "--",
+            ".",
+        ],
+    )?;
+    let mut entries = Vec::new();
+    for line in output.split(|c| *c == 0).filter(|l| !l.is_empty()) {
+        let line = String::from_utf8_lossy(line);
+        let (info, path) = match line.split_once('\t') {
+            Some(parts) => parts,
+            None => continue,
+        };
+        let info = info.split_whitespace().collect::<Vec<_>>();
+        if let [_mode, "blob", id, size] = info.as_slice() {
+            entries.push(TreeEntry {
+                path: path.to_string(),
+                id: id.to_string(),
+                size: size.parse().unwrap_or(0),
+            });
+        }
+    }
+    Ok(entries)
+}
+
+/// Read the contents of the given blobs in one go, in the same order as the ids
+pub(crate) fn read_blobs(folder: &Path, ids: &[&str]) -> Result<Vec<Vec<u8>>> {
+    let args = ["cat-file", "--batch"];
+    let mut child = Command::new("git")
+        .arg("-C")
+        .arg(folder)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| Error::git(&args, e.to_string()))?;
+
+    // Write the ids from a separate thread so git cannot block on a full output pipe
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = ids.iter().map(|id| format!("{}\n", id)).collect::<String>();
+    let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+
+    let mut stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+    let mut blobs = Vec::with_capacity(ids.len());
+    for id in ids {
+        let mut header = String::new();
+        stdout
+            .read_line(&mut header)
+            .map_err(|e| Error::git(&args, e.to_string()))?;
+        let size = match header.split_whitespace().collect::<Vec<_>>().as_slice() {
+            [_, _, size] => size.parse::<usize>().unwrap_or(0),
+            _ => return Err(Error::git(&args, format!("could not read object {}", id))),
+        };
+        // The contents are followed by a newline
+        let mut contents = vec![0; size + 1];
+        stdout
+            .read_exact(&mut contents)
+            .map_err(|e| Error::git(&args, e.to_string()))?;
+        contents.pop();
+        blobs.push(contents);
+    }
+
+    let _ = writer.join();
+    let _ = child.wait();
+    Ok(blobs)
+}
@@ -0,0 +1,222 @@
+use crate::error::SyntaxError;
+use crate::refs::{Context, Extractor, Reference, ReferenceKind};
+use crate::structure::{join_path, parent_path};
+
+/// Finds the imports in Go source files. Go imports packages, so they are resolved to the
+/// folder of the package: imports starting with the module path in the nearest `go.mod` to
+/// the folder below that module, and others to the `vendor` folder of the module when they
+/// are vendored.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GoExtractor;
+
+impl Extractor for GoExtractor {
+    fn language(&self) -> &str {
+        "go"
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        path.ends_with(".go")
+    }
+
+    fn extract(&self, path: &str, contents: &str) -> Result<Vec<Reference>, SyntaxError> {
+        let folder = parent_path(path);
+        let mut refs = Vec::new();
+        let mut comment = false;
+        let mut group = false;
+        for line in contents.lines() {
+            let line = line.trim();
+            if comment || line.starts_with("/*") {
+                comment = !line.contains("*/");
+                continue;
+            }
+            let spec = if group {
+                if line.starts_with(')') {
+                    group = false;
+                    continue;
+                }
+                line
+            } else if let Some(rest) = line.strip_prefix("import") {
+                let rest = rest.trim_start();
+                match rest.strip_prefix('(') {
+                    Some(rest) => {
+                        // Unless the group ends on the same line, like `import ("a")`
+                        group = !rest.contains(')');
+                        rest
+                    }
+                    None => rest,
+                }
+            } else if ["func", "type", "var", "const"]
+                .iter()
+                .any(|keyword| line.starts_with(keyword))
+            {
+                // Imports come before all declarations
+                break;
+            } else {
+                continue;
+            };
+            // `"a/b"`, `name "a/b"`, `_ "a/b"` or `. "a/b"`
+            let start = match spec.find(['"', '`']) {
+                Some(start) if !spec[..start].contains("//") => start,
+                _ => continue,
+            };
+            let quote = &spec[start..start + 1];
+            if let Some((target, _)) = spec[start + 1..].split_once(quote) {
+                refs.push(Reference::new(ReferenceKind::Import, target, folder));
+            }
+        }
+        Ok(refs)
+    }
+
+    fn resolve(&self, path: &str, reference: &Reference, context: &Context) -> Option<String> {
+        // The module the file belongs to is in the nearest folder with a `go.mod`
+        let mut folder = parent_path(path);
+        while !context.exists(&join_path(folder, "go.mod")) {
+            if folder.is_empty() {
+                return None;
+            }
+            folder = parent_path(folder);
+        }
+        let module = context.parse(&join_path(folder, "go.mod"), module_path)?;
+        let target = reference.target.as_str();
+        let package = if target == module.as_str() {
+            folder.to_string()
+        } else if let Some(package) = target
+            .strip_prefix(module.as_str())
+            .and_then(|p| p.strip_prefix('/'))
+        {
+            join_path(folder, package)
+        } else {
+            join_path(folder, &format!("vendor/{}", target))
+        };
+        Some(format!("{}/", package))
+    }
+
+    fn reads(&self, path: &str) -> bool {
+        path == "go.mod" || path.ends_with("/go.mod")
+    }
+}
+
+/// The module path declared in the contents of a `go.mod`
+fn module_path(text: &str) -> Option<String> {
+    text.lines().find_map(|line| {
+        let module = line.trim().strip_prefix("module")?.trim();
+        let module = module.split("//").next().unwrap_or_default().trim();
+        Some(module.trim_matches('"').to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn targets(contents: &str) -> Vec<String> {
+        GoExtractor
+            .extract("cmd/main.go", contents)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.target)
+            .collect()
+    }
+
+    /// Resolve an import in a folder with the given files and `go.mod` contents
+    fn resolve(
+        path: &str,
+        target: &str,
+        files: &[&str],
+        modules: &[(&str, &str)],
+    ) -> Option<String> {
+        let exists = |p: &str| files.contains(&p);
+        let read = |p: &str| {
+            modules
+                .iter()
+                .find(|(module, _)| *module == p)
+                .map(|(_, text)| text.to_string())
+        };
+        let reference = Reference::new(ReferenceKind::Import, target, parent_path(path));
+        GoExtractor.resolve(path, &reference, &Context::new(&exists, &read))
+    }
+
+    #[test]
+    fn imports() {
+        let source = concat!(
+            "// Package main\n",
+            "package main\n",
+            "\n",
+            "import \"fmt\"\n",
+            "import alias \"example.com/m/a\"\n",
+            "import (\n",
+            "\t\"os\" // a comment\n",
+            "\t_ \"example.com/m/b\"\n",
+            "\t. `example.com/m/c`\n",
+            "\t// \"example.com/m/d\"\n",
+            ")\n",
+            "/*\n",
+            "import \"example.com/m/e\"\n",
+            "*/\n",
+            "import (\"example.com/m/f\")\n",
+            "\n",
+            "func main() {}\n",
+            "\n",
+            "import \"example.com/m/g\"\n",
+        );
+        assert_eq!(
+            targets(source),
+            vec![
+                "fmt",
+                "example.com/m/a",
+                "os",
+                "example.com/m/b",
+                "example.com/m/c",
+                "example.com/m/f"
+            ]
+        );
+    }
+
+    #[test]
+    fn module_packages() {
+        let files = ["go.mod", "main.go", "a/", "cmd/", "cmd/main.go"];
+        let modules = [(
+            "go.mod",
+            "// The module\nmodule \"example.com/m\" // comment\n",
+        )];
+        let resolve = |target| resolve("cmd/main.go", target, &files, &modules);
+        assert_eq!(resolve("example.com/m"), Some("/".to_string()));
+        assert_eq!(resolve("example.com/m/a"), Some("a/".to_string()));
+        assert_eq!(resolve("example.com/m/a/b"), Some("a/b/".to_string()));
+        // Other packages are looked for in the vendor folder, a package with the module
+        // path as prefix is not in the module
+        assert_eq!(resolve("fmt"), Some("vendor/fmt/".to_string()));
+        assert_eq!(
+            resolve("example.com/mod"),
+            Some("vendor/example.com/mod/".to_string())
+        );
+    }
+
+    #[test]
+    fn nearest_module() {
+        let files = [
+            "go.mod",
+            "svc/go.mod",
+            "svc/api/x.go",
+            "svc/vendor/github.com/x/y/",
+        ];
+        let modules = [
+            ("go.mod", "module example.com/m\n"),
+            ("svc/go.mod", "module example.com/m/svc\n"),
+        ];
+        assert_eq!(
+            resolve("svc/api/x.go", "example.com/m/svc/db", &files, &modules),
+            Some("svc/db/".to_string())
+        );
+        assert_eq!(
+            resolve("svc/api/x.go", "github.com/x/y", &files, &modules),
+            Some("svc/vendor/github.com/x/y/".to_string())
+        );
+        // Without a `go.mod` or a module path nothing is resolved
+        assert_eq!(
+            resolve("svc/api/x.go", "example.com/m/svc/db", &files, &[]),
+            None
+        );
+        assert_eq!(resolve("x.go", "example.com/m", &["x.go"], &[]), None);
+    }
+}
@@ -0,0 +1,159 @@
+use crate::error::*;
+use crate::git;
+
+use std::collections::HashMap;
+use std::path::Path;
+
+/// The git history of a single file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileHistory {
+    /// The number of commits that changed the file
+    pub commits: u64,
+    /// The total number of lines added over all commits
+    pub lines_added: u64,
+    /// The total number of lines removed over all commits
+    pub lines_removed: u64,
+    /// The commit time of the last commit that changed the file, in seconds since the epoch
+    pub last_modified: Option<i64>,
+    /// The commit time of the commit that added the file, in seconds since the epoch
+    pub created: Option<i64>,
+    /// The number of commits that changed the file per author, using the names from
+    /// `.mailmap` when present
+    pub authors: HashMap<String, u64>,
+    /// The number of commits that changed both this file and the other file, by path of the
+    /// other file. Commits changing more than [`MAX_COUPLED_FILES`] files are not counted.
+    pub coupled: HashMap<String, u64>,
+}
+
+impl FileHistory {
+    /// The total number of lines changed
+    pub fn churn(&self) -> u64 {
+        self.lines_added + self.lines_removed
+    }
+
+    /// The number of lines the file has now, the lines added minus the lines removed. As
+    /// renames are not followed every file starts with all its lines added.
+    pub fn lines(&self) -> u64 {
+        self.lines_added.saturating_sub(self.lines_removed)
+    }
+
+    /// How much the file is worth refactoring first, the number of commits times the number
+    /// of lines, as large files that change often are the hardest to work with
+    pub fn hotspot(&self) -> u64 {
+        self.commits * self.lines()
+    }
+
+    /// The author with the most commits to the file together with their number of
+    /// commits, on a tie the alphabetically first author is picked
+    pub fn primary_author(&self) -> Option<(&str, u64)> {
+        self.authors
+            .iter()
+            .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))
+            .map(|(name, commits)| (name.as_str(), *commits))
+    }
+}
+
+/// Commits that change more files than this (like big reformats or moves) do not count
+/// towards the coupling between files, as they say little about which files belong together
+pub const MAX_COUPLED_FILES: usize = 50;
+
+/// The git history of all files below a folder
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    /// The history of every file that was ever changed, by path relative to the folder
+    pub files: HashMap<String, FileHistory>,
+}
+
+/// Walk the git history of the given folder, up to the given revision or `HEAD`.
+/// Renames are not followed, a renamed file starts with a fresh history. Author names
+/// are mapped with the `.mailmap` of the repository.
+pub fn get_history(path: &Path, revision: Option<&str>) -> Result<History> {
+    let output = git::run(
+        path,
+        &[
+            "-c",
+            "core.quotePath=false",
+            "log",
+            "--numstat",
+            "--no-renames",
+            "--relative",
+            "--format=commit %H %ct %aN",
+            "--end-of-options",
+            revision.unwrap_or("HEAD"),
+            "--",
+            ".",
+        ],
+    )?;
+    let mut history = History::default();
+    let mut time = None;
+    let mut author = String::new();
+    let mut changed = Vec::new();
+    for line in String::from_utf8_lossy(&output).lines() {
+        if let Some(commit) = line.strip_prefix("commit ") {
+            add_coupling(&mut history, &changed);
+            changed.clear();
+            let mut parts = commit.splitn(3, ' ').skip(1);
+            time = parts.next().and_then(|t| t.parse::<i64>().ok());
+            author = parts.next().unwrap_or_default().to_string();
+            continue;
+        }
+        let mut parts = line.splitn(3, '\t');
+        if let (Some(added), Some(removed), Some(file)) = (parts.next(), parts.next(), parts.next())
+        {
+            let file_history = history.files.entry(file.to_string()).or_default();
+            file_history.commits += 1;
+            // Binary files are listed with `-` for both numbers
+            file_history.lines_added += added.parse::<u64>().unwrap_or(0);
+            file_history.lines_removed += removed.parse::<u64>().unwrap_or(0);
+            // The commits are listed from new to old
+            if file_history.last_modified.is_none() {
+                file_history.last_modified = time;
+            }
+            file_history.created = time;
+            *file_history.authors.entry(author.clone()).or_default() += 1;
+            changed.push(file.to_string());
+        }
+    }
+    add_coupling(&mut history, &changed);
+    Ok(history)
+}
+
+/// Count a commit towards the coupling of every pair of the files changed in it
+fn add_coupling(history: &mut History, changed: &[String]) {
+    if changed.len() > MAX_COUPLED_FILES {
+        return;
+    }
+    for file in changed {
+        if let Some(file_history) = history.files.get_mut(file) {
+            for other in changed.iter().filter(|other| *other != file) {
+                *file_history.coupled.entry(other.clone()).or_default() += 1;
+            }
+        }
+    }
+}
+
+/// Format a time in seconds since the epoch as a UTC date (`YYYY-MM-DD`)
+pub fn format_date(time: i64) -> String {
+    let (year, month, day) = civil_date(time);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// The UTC year, month and day of a time in seconds since the epoch
+pub(crate) fn civil_date(time: i64) -> (i64, i64, i64) {
+    // Convert the days since the epoch to a civil date (Howard Hinnant's algorithm)
+    let days = time.div_euclid(86400) + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
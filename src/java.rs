@@ -0,0 +1,253 @@
+use crate::error::SyntaxError;
+use crate::refs::{Context, Extractor, Reference, ReferenceKind};
+use crate::structure::{join_path, parent_path};
+
+/// The source sets next to the one of a file where imports are looked for as well
+const SOURCE_SETS: [&str; 4] = ["main/java", "main/kotlin", "test/java", "test/kotlin"];
+
+/// The starts of the declarations that follow the imports
+const DECLARATIONS: [&str; 6] = [
+    "class ",
+    "interface ",
+    "enum ",
+    "object ",
+    "fun ",
+    "public ",
+];
+
+/// Finds the imports in Java and Kotlin source files. The `package` of a file gives the
+/// source folder it is in, imports are resolved to the file of the imported class in that
+/// source folder or in the other source sets of the module (like `src/main/java` and
+/// `src/test/kotlin`), and otherwise to the folder of the imported package.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JavaExtractor;
+
+impl Extractor for JavaExtractor {
+    fn language(&self) -> &str {
+        "java"
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        path.ends_with(".java") || path.ends_with(".kt") || path.ends_with(".kts")
+    }
+
+    fn extract(&self, path: &str, contents: &str) -> Result<Vec<Reference>, SyntaxError> {
+        let mut package = None;
+        let mut imports = Vec::new();
+        let mut comment = false;
+        for line in contents.lines() {
+            let line = line.trim();
+            if comment || line.starts_with("/*") {
+                comment = !line.contains("*/");
+                continue;
+            }
+            let line = line.trim_end_matches(';');
+            if let Some(name) = line.strip_prefix("package ") {
+                package = Some(name.trim().to_string());
+            } else if let Some(import) = line.strip_prefix("import ") {
+                // `import a.b.C`, `import a.b.*`, `import static a.b.C.d` and `import a.b.C as D`
+                let import = import.trim_start();
+                let import = import.strip_prefix("static ").unwrap_or(import);
+                if let Some(name) = import.split_whitespace().next() {
+                    imports.push(name.to_string());
+                }
+            } else if DECLARATIONS.iter().any(|d| line.starts_with(d)) {
+                // Imports come before all declarations
+                break;
+            }
+        }
+        // The source folder is the folder of the file without the folders of the package
+        let folder = parent_path(path);
+        let package_folder = package.as_deref().unwrap_or_default().replace('.', "/");
+        let root = if folder == package_folder {
+            ""
+        } else {
+            folder
+                .strip_suffix(package_folder.as_str())
+                .and_then(|root| root.strip_suffix('/'))
+                .unwrap_or(folder)
+        };
+        Ok(imports
+            .into_iter()
+            .map(|import| Reference::new(ReferenceKind::Import, import, root))
+            .collect())
+    }
+
+    fn resolve(&self, _: &str, reference: &Reference, context: &Context) -> Option<String> {
+        let exists = &|p: &str| context.exists(p);
+        let segments = reference
+            .target
+            .split('.')
+            .filter(|s| *s != "*")
+            .collect::<Vec<_>>();
+        let root = reference.scope.as_str();
+        let mut roots = vec![root.to_string()];
+        // `module/src/main/java` also sees `module/src/test/kotlin` and the like
+        if let Some(index) = root
+            .rfind("src/")
+            .filter(|i| *i == 0 || root[..*i].ends_with('/'))
+        {
+            for set in SOURCE_SETS {
+                let set = join_path(&root[..index + 3], set);
+                if !roots.contains(&set) {
+                    roots.push(set);
+                }
+            }
+        }
+        // The imported name is a class, a package or a member of one of them
+        let lengths = (segments.len().saturating_sub(1).max(1)..=segments.len()).rev();
+        for length in lengths.clone() {
+            for root in &roots {
+                let path = join_path(root, &segments[..length].join("/"));
+                let found = ["java", "kt"]
+                    .iter()
+                    .map(|extension| format!("{}.{}", path, extension))
+                    .find(|c| exists(c));
+                if found.is_some() {
+                    return found;
+                }
+            }
+        }
+        for length in lengths {
+            for root in &roots {
+                let folder = format!("{}/", join_path(root, &segments[..length].join("/")));
+                if exists(&folder) {
+                    return Some(folder);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extracted(path: &str, contents: &str) -> Vec<(String, String)> {
+        JavaExtractor
+            .extract(path, contents)
+            .unwrap()
+            .into_iter()
+            .map(|r| (r.target, r.scope))
+            .collect()
+    }
+
+    fn resolve(scope: &str, target: &str, files: &[&str]) -> Option<String> {
+        let exists = |p: &str| files.contains(&p);
+        let reference = Reference::new(ReferenceKind::Import, target, scope);
+        JavaExtractor.resolve("", &reference, &Context::new(&exists, &|_| None))
+    }
+
+    #[test]
+    fn imports() {
+        let source = concat!(
+            "/*\n",
+            " * import a.Commented;\n",
+            " */\n",
+            "package com.example.app;\n",
+            "\n",
+            "import java.util.List;\n",
+            "import static com.example.util.Strings.join;\n",
+            "import com.example.model.*;\n",
+            "\n",
+            "public class App {\n",
+            "    import a.Late;\n",
+            "}\n",
+        );
+        let scope = "app/src/main/java".to_string();
+        assert_eq!(
+            extracted("app/src/main/java/com/example/app/App.java", source),
+            vec![
+                ("java.util.List".to_string(), scope.clone()),
+                ("com.example.util.Strings.join".to_string(), scope.clone()),
+                ("com.example.model.*".to_string(), scope),
+            ]
+        );
+        let source =
+            "package com.example\n\nimport com.example.util.Strings as S\n\nfun main() {}\n";
+        assert_eq!(
+            extracted("src/main/kotlin/com/example/Main.kt", source),
+            vec![(
+                "com.example.util.Strings".to_string(),
+                "src/main/kotlin".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn source_folders() {
+        let import = "import a.B;\n";
+        // The scanned folder is the source folder
+        assert_eq!(
+            extracted("com/x/A.java", &format!("package com.x;\n{}", import)),
+            vec![("a.B".to_string(), String::new())]
+        );
+        // Without a package, or with one that does not match the folders, the folder of
+        // the file is the source folder
+        assert_eq!(
+            extracted("src/A.java", import),
+            vec![("a.B".to_string(), "src".to_string())]
+        );
+        assert_eq!(
+            extracted("src/A.java", &format!("package com.x;\n{}", import)),
+            vec![("a.B".to_string(), "src".to_string())]
+        );
+    }
+
+    #[test]
+    fn classes_members_and_packages() {
+        let files = [
+            "src/main/java/com/x/Util.java",
+            "src/main/java/com/x/model/",
+            "src/main/java/com/x/model/User.kt",
+        ];
+        let root = "src/main/java";
+        let util = Some("src/main/java/com/x/Util.java".to_string());
+        assert_eq!(resolve(root, "com.x.Util", &files), util);
+        assert_eq!(resolve(root, "com.x.Util.join", &files), util);
+        assert_eq!(resolve(root, "com.x.Util.*", &files), util);
+        assert_eq!(
+            resolve(root, "com.x.model.User", &files),
+            Some("src/main/java/com/x/model/User.kt".to_string())
+        );
+        assert_eq!(
+            resolve(root, "com.x.model.*", &files),
+            Some("src/main/java/com/x/model/".to_string())
+        );
+        assert_eq!(resolve(root, "java.util.List", &files), None);
+    }
+
+    #[test]
+    fn source_sets() {
+        let files = [
+            "app/src/main/java/com/x/App.java",
+            "app/src/main/kotlin/com/x/Extensions.kt",
+            "app/src/test/java/com/x/Fixtures.java",
+            "lib/src/main/java/com/x/Lib.java",
+        ];
+        let test = "app/src/test/kotlin";
+        assert_eq!(
+            resolve(test, "com.x.App", &files),
+            Some("app/src/main/java/com/x/App.java".to_string())
+        );
+        assert_eq!(
+            resolve(test, "com.x.Extensions", &files),
+            Some("app/src/main/kotlin/com/x/Extensions.kt".to_string())
+        );
+        assert_eq!(
+            resolve("app/src/main/java", "com.x.Fixtures", &files),
+            Some("app/src/test/java/com/x/Fixtures.java".to_string())
+        );
+        // Other modules are not looked in
+        assert_eq!(resolve(test, "com.x.Lib", &files), None);
+        assert_eq!(
+            resolve(
+                "src/main/java",
+                "com.x.App",
+                &["src/test/java/com/x/App.java"]
+            ),
+            Some("src/test/java/com/x/App.java".to_string())
+        );
+    }
+}
@@ -0,0 +1,611 @@
+use crate::error::SyntaxError;
+use crate::refs::{Context, Extractor, Reference, ReferenceKind};
+use crate::structure::{join_path, parent_path};
+
+use serde_json::Value;
+use std::iter::Peekable;
+use std::rc::Rc;
+use std::str::Chars;
+
+/// The extensions tried for a specifier without one, in the order TypeScript tries them
+const EXTENSIONS: [&str; 10] = [
+    "ts", "tsx", "d.ts", "js", "jsx", "mjs", "cjs", "mts", "cts", "json",
+];
+
+/// Finds the imports in JavaScript and TypeScript source files: `import` (also dynamic
+/// `import()`), `require()` and `export ... from`. Relative specifiers are resolved like
+/// Node and TypeScript do, trying extensions and `index` files. Other specifiers are
+/// resolved with the `baseUrl` and `paths` of the nearest `tsconfig.json` or
+/// `jsconfig.json`, or are left out as they refer to packages.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JavaScriptExtractor;
+
+impl Extractor for JavaScriptExtractor {
+    fn language(&self) -> &str {
+        "javascript"
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        let extension = path.rsplit_once('.').map_or("", |(_, e)| e);
+        matches!(
+            extension,
+            "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "mts" | "cts"
+        )
+    }
+
+    fn extract(&self, path: &str, contents: &str) -> Result<Vec<Reference>, SyntaxError> {
+        let folder = parent_path(path);
+        let tokens = tokenize(contents);
+        let mut refs = Vec::new();
+        for (index, token) in tokens.iter().enumerate() {
+            let specifier = match token {
+                Token::String(s) => s,
+                _ => continue,
+            };
+            let before = |n: usize| index.checked_sub(n).and_then(|i| tokens.get(i));
+            // `import 'a'`, `... from 'a'`, `import('a')` and `require('a')`
+            let is_import = is_word(before(1), "import")
+                || is_word(before(1), "from")
+                || (before(1) == Some(&Token::Punct('('))
+                    && (is_word(before(2), "import") || is_word(before(2), "require")));
+            if is_import {
+                refs.push(Reference::new(ReferenceKind::Import, specifier, folder));
+            }
+        }
+        Ok(refs)
+    }
+
+    fn resolve(&self, path: &str, reference: &Reference, context: &Context) -> Option<String> {
+        // The nearest configuration applies
+        let exists = |p: &str| context.exists(p);
+        let mut folder = Some(parent_path(path));
+        let mut config = None;
+        while let Some(current) = folder {
+            let found = ["tsconfig.json", "jsconfig.json"]
+                .iter()
+                .map(|name| join_path(current, name))
+                .find(|p| exists(p));
+            if let Some(found) = found {
+                config = read_config(context, &found, 0);
+                break;
+            }
+            folder = (!current.is_empty()).then(|| parent_path(current));
+        }
+        resolve(path, reference, &exists, config.as_deref())
+    }
+
+    fn reads(&self, path: &str) -> bool {
+        // Also `tsconfig.base.json` and the like, which are often extended
+        let name = path.rsplit('/').next().unwrap_or_default();
+        (name.starts_with("tsconfig") || name.starts_with("jsconfig")) && name.ends_with(".json")
+    }
+}
+
+/// Read the configuration in the given file, following `extends` to other files in the
+/// folder. Every file is only read once per scan.
+fn read_config(context: &Context, path: &str, depth: usize) -> Option<Rc<Config>> {
+    context.parse(path, |text| parse_config(context, path, text, depth))
+}
+
+/// Parse the contents of the configuration at the given path
+fn parse_config(context: &Context, path: &str, text: &str, depth: usize) -> Option<Config> {
+    let json = serde_json::from_str::<Value>(&strip_json(text)).ok()?;
+    let folder = parent_path(path);
+    // Settings that are not set are inherited from the extended configuration
+    let mut config = json
+        .get("extends")
+        .and_then(Value::as_str)
+        .filter(|e| e.starts_with('.') && depth < 8)
+        .and_then(|extends| {
+            let extends = join_path(folder, extends);
+            let extends = if extends.ends_with(".json") {
+                extends
+            } else {
+                format!("{}.json", extends)
+            };
+            read_config(context, &extends, depth + 1)
+        })
+        .map(|c| c.as_ref().clone())
+        .unwrap_or_default();
+    let options = json.get("compilerOptions");
+    let base_url = options
+        .and_then(|o| o.get("baseUrl"))
+        .and_then(Value::as_str)
+        .map(|b| join_path(folder, b));
+    if let Some(paths) = options
+        .and_then(|o| o.get("paths"))
+        .and_then(Value::as_object)
+    {
+        // Paths are relative to the base url, also an inherited one, or to the
+        // configuration without one
+        let base = base_url
+            .as_deref()
+            .or(config.base_url.as_deref())
+            .unwrap_or(folder);
+        config.paths = paths
+            .iter()
+            .map(|(pattern, targets)| {
+                let targets = targets
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Value::as_str)
+                    .map(|t| join_path(base, t))
+                    .collect();
+                (pattern.clone(), targets)
+            })
+            .collect();
+    }
+    if base_url.is_some() {
+        config.base_url = base_url;
+    }
+    Some(config)
+}
+
+/// The module resolution settings of a `tsconfig.json`, with all paths relative to the
+/// scanned folder
+#[derive(Debug, Clone, Default)]
+struct Config {
+    base_url: Option<String>,
+    /// The patterns of `paths` with the paths they map to
+    paths: Vec<(String, Vec<String>)>,
+}
+
+fn resolve(
+    path: &str,
+    reference: &Reference,
+    exists: &dyn Fn(&str) -> bool,
+    config: Option<&Config>,
+) -> Option<String> {
+    let specifier = reference.target.as_str();
+    if specifier.starts_with("./") || specifier.starts_with("../") || specifier == "." {
+        return find_file(&join_path(parent_path(path), specifier), exists);
+    }
+    let config = config?;
+    // The pattern with the longest prefix before the `*` wins
+    let mut patterns = config
+        .paths
+        .iter()
+        .filter_map(|(pattern, targets)| {
+            let matched = match pattern.split_once('*') {
+                Some((prefix, suffix)) => specifier
+                    .strip_prefix(prefix)
+                    .and_then(|s| s.strip_suffix(suffix))
+                    .map(|star| (prefix.len(), star)),
+                None => (pattern == specifier).then_some((pattern.len(), "")),
+            };
+            matched.map(|(length, star)| (length, star, targets))
+        })
+        .collect::<Vec<_>>();
+    patterns.sort_by_key(|(length, ..)| std::cmp::Reverse(*length));
+    patterns
+        .into_iter()
+        .flat_map(|(_, star, targets)| targets.iter().map(move |t| t.replacen('*', star, 1)))
+        .find_map(|target| find_file(&target, exists))
+        .or_else(|| find_file(&join_path(config.base_url.as_ref()?, specifier), exists))
+}
+
+/// Find the file a path without or with an extension refers to, or the `index` file in
+/// the folder it refers to
+fn find_file(path: &str, exists: &dyn Fn(&str) -> bool) -> Option<String> {
+    if exists(path) {
+        return Some(path.to_string());
+    }
+    // TypeScript sources are imported with the extension of the compiled file
+    let stem = [".js", ".jsx", ".mjs", ".cjs"]
+        .iter()
+        .find_map(|e| path.strip_suffix(e));
+    let mut candidates = Vec::new();
+    if let Some(stem) = stem {
+        candidates.extend(["ts", "tsx", "mts", "cts"].map(|e| format!("{}.{}", stem, e)));
+    }
+    candidates.extend(EXTENSIONS.map(|e| format!("{}.{}", path, e)));
+    candidates.extend(EXTENSIONS.map(|e| join_path(path, &format!("index.{}", e))));
+    candidates.into_iter().find(|c| exists(c))
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    String(String),
+    Punct(char),
+}
+
+fn is_word(token: Option<&Token>, word: &str) -> bool {
+    matches!(token, Some(Token::Word(w)) if w == word)
+}
+
+/// The keywords after which a `/` starts a regular expression instead of a division
+const KEYWORDS: [&str; 14] = [
+    "return",
+    "typeof",
+    "case",
+    "do",
+    "else",
+    "in",
+    "instanceof",
+    "new",
+    "delete",
+    "void",
+    "throw",
+    "yield",
+    "await",
+    "of",
+];
+
+/// Split source into words, string literals and punctuation, leaving out comments and
+/// regular expressions. Template literals are kept as strings when they have no
+/// substitutions.
+fn tokenize(contents: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = contents.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.next_if_eq(&'/').is_some() => {
+                while chars.next_if(|c| *c != '\n').is_some() {}
+            }
+            '/' if chars.next_if_eq(&'*').is_some() => {
+                let mut star = false;
+                for c in chars.by_ref() {
+                    if star && c == '/' {
+                        break;
+                    }
+                    star = c == '*';
+                }
+            }
+            '/' if starts_regex(tokens.last()) => {
+                let mut class = false;
+                while let Some(r) = chars.next() {
+                    match r {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '[' => class = true,
+                        ']' => class = false,
+                        '/' if !class => break,
+                        '\n' => break,
+                        _ => {}
+                    }
+                }
+                tokens.push(Token::Punct('/'));
+            }
+            '"' | '\'' | '`' => tokens.push(match read_string(&mut chars, c) {
+                Some(value) => Token::String(value),
+                None => Token::Punct('`'),
+            }),
+            c if c.is_alphanumeric() || c == '_' || c == '$' => {
+                let mut word = c.to_string();
+                while let Some(w) = chars.next_if(|c| c.is_alphanumeric() || *c == '_' || *c == '$')
+                {
+                    word.push(w);
+                }
+                tokens.push(Token::Word(word));
+            }
+            c if c.is_whitespace() => {}
+            c => tokens.push(Token::Punct(c)),
+        }
+    }
+    tokens
+}
+
+/// Whether a `/` after the given token starts a regular expression, it is a division after
+/// a value
+fn starts_regex(token: Option<&Token>) -> bool {
+    match token {
+        None => true,
+        Some(Token::Word(word)) => KEYWORDS.contains(&word.as_str()),
+        Some(Token::String(_)) => false,
+        Some(Token::Punct(c)) => !matches!(c, ')' | ']'),
+    }
+}
+
+/// Read a string literal after its opening quote, `None` for a template literal with
+/// substitutions
+fn read_string(chars: &mut Peekable<Chars>, quote: char) -> Option<String> {
+    let mut value = String::new();
+    let mut substituted = false;
+    while let Some(s) = chars.next() {
+        match s {
+            '\\' => {
+                chars.next();
+            }
+            s if s == quote => break,
+            '\n' if quote != '`' => break,
+            '$' if quote == '`' && chars.next_if_eq(&'{').is_some() => {
+                substituted = true;
+                skip_substitution(chars);
+            }
+            s => value.push(s),
+        }
+    }
+    (!substituted).then_some(value)
+}
+
+/// Skip the expression of a `${...}` in a template literal, which can have strings and
+/// templates of its own
+fn skip_substitution(chars: &mut Peekable<Chars>) {
+    let mut depth = 1;
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return;
+                }
+            }
+            '"' | '\'' | '`' => {
+                read_string(chars, c);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Remove the comments and trailing commas `tsconfig.json` allows but JSON does not
+fn strip_json(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                stripped.push(c);
+                while let Some(s) = chars.next() {
+                    stripped.push(s);
+                    match s {
+                        '\\' => stripped.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '/' if chars.next_if_eq(&'/').is_some() => {
+                while chars.next_if(|c| *c != '\n').is_some() {}
+            }
+            '/' if chars.next_if_eq(&'*').is_some() => {
+                let mut star = false;
+                for c in chars.by_ref() {
+                    if star && c == '/' {
+                        break;
+                    }
+                    star = c == '*';
+                }
+            }
+            '}' | ']' => {
+                let trimmed = stripped.trim_end().len();
+                if stripped[..trimmed].ends_with(',') {
+                    stripped.truncate(trimmed - 1);
+                }
+                stripped.push(c);
+            }
+            c => stripped.push(c),
+        }
+    }
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn word(w: &str) -> Token {
+        Token::Word(w.to_string())
+    }
+
+    fn string(s: &str) -> Token {
+        Token::String(s.to_string())
+    }
+
+    fn targets(contents: &str) -> Vec<String> {
+        JavaScriptExtractor
+            .extract("src/a.ts", contents)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.target)
+            .collect()
+    }
+
+    /// Resolve a specifier in a folder with the given files and contents
+    fn resolve(path: &str, target: &str, files: &[(&str, &str)]) -> Option<String> {
+        let contents = files.iter().copied().collect::<HashMap<_, _>>();
+        let exists = |p: &str| contents.contains_key(p);
+        let read = |p: &str| contents.get(p).map(|c| c.to_string());
+        let reference = Reference::new(ReferenceKind::Import, target, parent_path(path));
+        JavaScriptExtractor.resolve(path, &reference, &Context::new(&exists, &read))
+    }
+
+    #[test]
+    fn tokenize_template_literals() {
+        assert_eq!(
+            tokenize("import(`./a`)"),
+            vec![
+                word("import"),
+                Token::Punct('('),
+                string("./a"),
+                Token::Punct(')')
+            ]
+        );
+        // A template with substitutions is not a specifier, also with templates inside them
+        assert_eq!(
+            tokenize("x = `${`}`}`; require('./b')"),
+            vec![
+                word("x"),
+                Token::Punct('='),
+                Token::Punct('`'),
+                Token::Punct(';'),
+                word("require"),
+                Token::Punct('('),
+                string("./b"),
+                Token::Punct(')'),
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenize_regular_expressions_and_divisions() {
+        // The quotes in a regular expression do not start a string
+        assert_eq!(
+            targets("const re = /['\"]/g;\nimport a from './a'\n"),
+            vec!["./a"]
+        );
+        assert_eq!(
+            targets("if (x) return /[/]'/.test(y); import('./b')"),
+            vec!["./b"]
+        );
+        // Divisions are not regular expressions
+        assert_eq!(
+            tokenize("a / b / 'c'"),
+            vec![
+                word("a"),
+                Token::Punct('/'),
+                word("b"),
+                Token::Punct('/'),
+                string("c"),
+            ]
+        );
+        assert_eq!(
+            tokenize("(a) / 2 / 'c'"),
+            vec![
+                Token::Punct('('),
+                word("a"),
+                Token::Punct(')'),
+                Token::Punct('/'),
+                word("2"),
+                Token::Punct('/'),
+                string("c"),
+            ]
+        );
+    }
+
+    #[test]
+    fn imports() {
+        let source = concat!(
+            "import a from './a';\n",
+            "import './b';\n",
+            "export * from \"./c\";\n",
+            "const d = require('./d'); // require('./e')\n",
+            "/* import './f' */\n",
+            "const g = await import('./g');\n",
+            "const h = 'import';\n",
+        );
+        assert_eq!(targets(source), vec!["./a", "./b", "./c", "./d", "./g"]);
+    }
+
+    #[test]
+    fn strip_json_comments_and_trailing_commas() {
+        let text = concat!(
+            "{\n",
+            "  // a comment\n",
+            "  \"a\": \"http://x/*y*/\", /* another */\n",
+            "  \"b\": [1, 2,],\n",
+            "  \"c\": \"\\\"//\",\n",
+            "}\n",
+        );
+        let value = serde_json::from_str::<Value>(&strip_json(text)).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({"a": "http://x/*y*/", "b": [1, 2], "c": "\"//"})
+        );
+    }
+
+    #[test]
+    fn relative_specifiers() {
+        let files = [
+            ("src/a.ts", ""),
+            ("src/b.ts", ""),
+            ("src/c/index.tsx", ""),
+            ("src/d.js", ""),
+        ];
+        assert_eq!(
+            resolve("src/a.ts", "./b", &files),
+            Some("src/b.ts".to_string())
+        );
+        assert_eq!(
+            resolve("src/a.ts", "./b.js", &files),
+            Some("src/b.ts".to_string())
+        );
+        assert_eq!(
+            resolve("src/a.ts", "./c", &files),
+            Some("src/c/index.tsx".to_string())
+        );
+        assert_eq!(
+            resolve("src/a.ts", "./d.js", &files),
+            Some("src/d.js".to_string())
+        );
+        assert_eq!(resolve("src/a.ts", "react", &files), None);
+    }
+
+    #[test]
+    fn tsconfig_paths() {
+        let tsconfig = r#"{
+            "compilerOptions": {
+                "baseUrl": "src",
+                "paths": {
+                    "@/*": ["*"],
+                    "@lib/*": ["../lib/*", "../vendor/*"],
+                    "config": ["settings/index.ts"],
+                },
+            },
+        }"#;
+        let files = [
+            ("tsconfig.json", tsconfig),
+            ("src/app/main.ts", ""),
+            ("src/app/util.ts", ""),
+            ("src/settings/index.ts", ""),
+            ("src/models.ts", ""),
+            ("vendor/x.ts", ""),
+        ];
+        let from = "src/app/main.ts";
+        assert_eq!(
+            resolve(from, "@/app/util", &files),
+            Some("src/app/util.ts".to_string())
+        );
+        // The targets are tried in order
+        assert_eq!(
+            resolve(from, "@lib/x", &files),
+            Some("vendor/x.ts".to_string())
+        );
+        assert_eq!(
+            resolve(from, "config", &files),
+            Some("src/settings/index.ts".to_string())
+        );
+        // Through the base url
+        assert_eq!(
+            resolve(from, "models", &files),
+            Some("src/models.ts".to_string())
+        );
+        assert_eq!(resolve(from, "react", &files), None);
+    }
+
+    #[test]
+    fn tsconfig_extends() {
+        let files = [
+            (
+                "configs/base.json",
+                r#"{"compilerOptions": {"baseUrl": "../src"}}"#,
+            ),
+            (
+                "configs/paths.json",
+                r#"{"extends": "./base", "compilerOptions": {"paths": {"~/*": ["lib/*"]}}}"#,
+            ),
+            (
+                "app/tsconfig.json",
+                r#"{"extends": "../configs/paths.json"}"#,
+            ),
+            ("app/main.ts", ""),
+            ("src/models.ts", ""),
+            ("src/lib/util.ts", ""),
+        ];
+        // The base url of the first configuration and the paths of the second, relative to
+        // that base url
+        assert_eq!(
+            resolve("app/main.ts", "models", &files),
+            Some("src/models.ts".to_string())
+        );
+        assert_eq!(
+            resolve("app/main.ts", "~/util", &files),
+            Some("src/lib/util.ts".to_string())
+        );
+    }
+}
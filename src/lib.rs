@@ -0,0 +1,115 @@
+//! Visualise the structure of a repository as a zoomable circle diagram.
+//!
+//! Making a plot happens in three steps, which can also be done in one go with [`plot`]:
+//! 1. Scanning the repository into an [`Item`] tree with [`get_structure`], or
+//!    [`get_revision_structure`] for a committed revision, or [`get_diff_structure`] for the
+//!    changes between two revisions, or [`get_patch_structure`] for the files touched by a
+//!    patch. Problems with single files are collected as [`Warning`]s instead of stopping
+//!    the scan. Optionally the git history of every file is added with
+//!    [`Item::add_history`], the state of the working tree with [`Item::add_status`],
+//!    the crates of a Cargo workspace with [`Item::add_workspace`], and the external
+//!    packages from the lock files with [`Item::add_dependencies`].
+//! 2. Laying out the tree as circles with [`layout`].
+//! 3. Rendering the layout into an interactive SVG with [`render`] and saving it with [`save`].
+//!
+//! ```no_run
+//! use git_cuttle::*;
+//! use std::path::Path;
+//!
+//! let scan = get_structure(Path::new("."), &ScanOptions::default())?;
+//! for warning in &scan.warnings {
+//!     eprintln!("warning: {}", warning);
+//! }
+//! let options = PlotOptions::default();
+//! let layout = layout(&scan.root, &options);
+//! let document = render(&layout, &options);
+//! save(&document, Path::new("plot.svg"), Format::Svg, scan.root.name())?;
+//! # Ok::<(), git_cuttle::Error>(())
+//! ```
+//!
+//! The references between files are found by the [`Extractor`]s in
+//! [`ScanOptions::extractors`], register an extractor to support another language or to
+//! replace the built in one of a language:
+//!
+//! ```no_run
+//! # use git_cuttle::*;
+//! struct MyExtractor;
+//!
+//! impl Extractor for MyExtractor {
+//!     fn language(&self) -> &str {
+//!         "my-language"
+//!     }
+//!     fn matches(&self, path: &str) -> bool {
+//!         path.ends_with(".my")
+//!     }
+//!     fn extract(
+//!         &self,
+//!         path: &str,
+//!         contents: &str,
+//!     ) -> std::result::Result<Vec<Reference>, SyntaxError> {
+//!         let folder = path.rsplit_once('/').map_or("", |(folder, _)| folder);
+//!         Ok(contents
+//!             .lines()
+//!             .filter_map(|line| line.strip_prefix("import "))
+//!             .map(|target| Reference::new(ReferenceKind::Include, target, folder))
+//!             .collect())
+//!     }
+//!     fn resolve(&self, _: &str, reference: &Reference, _: &Context) -> Option<String> {
+//!         Some(format!("{}/{}", reference.scope, reference.target))
+//!     }
+//! }
+//!
+//! let mut options = ScanOptions::default();
+//! options.extractors.register(MyExtractor);
+//! let scan = get_structure(std::path::Path::new("."), &options)?;
+//! # Ok::<(), git_cuttle::Error>(())
+//! ```
+//!
+//! The growth of a repository over time can be animated with [`get_timeline`] and [`plot_timeline`].
+mod c;
+mod cargo;
+mod colour;
+mod diff;
+mod error;
+mod filter;
+mod git;
+mod go;
+mod history;
+mod java;
+mod javascript;
+mod lockfile;
+mod patch;
+mod plot;
+mod python;
+mod refs;
+mod rust;
+mod status;
+mod structs;
+mod structure;
+mod timeline;
+mod web;
+
+pub use c::CExtractor;
+pub use cargo::{get_workspace, Package, Workspace};
+pub use colour::{heat, ColourMode, ColourScheme, SizeMode};
+pub use diff::{get_diff_structure, parse_range, Change};
+pub use error::{Error, Result, SyntaxError, Warning, WarningKind};
+pub use filter::PathFilter;
+pub use go::GoExtractor;
+pub use history::{format_date, get_history, FileHistory, History, MAX_COUPLED_FILES};
+pub use java::JavaExtractor;
+pub use javascript::JavaScriptExtractor;
+pub use lockfile::{get_dependencies, Dependency, Ecosystem};
+pub use patch::{get_patch_structure, parse_patch, FilePatch};
+pub use plot::{
+    layout, plot, plot_timeline, render, save, save_hotspots, CouplingOptions, Entity, EntityNode,
+    Format, PlotOptions,
+};
+pub use python::PythonExtractor;
+pub use refs::{Context, Extractor, Extractors, Reference, ReferenceKind};
+pub use rust::RustExtractor;
+pub use status::{get_status, Status};
+pub use structs::{Area, Point};
+pub use structure::{get_revision_structure, get_structure, FileType, Item, Scan, ScanOptions};
+pub use timeline::{get_timeline, Frame, FrameStep, Timeline};
+pub use web::WebExtractor;
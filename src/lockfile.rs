@@ -0,0 +1,303 @@
+use crate::cargo::get_workspace;
+use crate::error::*;
+
+use serde_json::Value as Json;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use toml::{Table, Value};
+
+/// The package manager an external package comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Ecosystem {
+    /// A crate from `Cargo.lock`
+    Cargo,
+    /// A package from `package-lock.json`
+    Npm,
+    /// A package from `poetry.lock`
+    Python,
+}
+
+impl Ecosystem {
+    /// The name of the ecosystem, also used as class in the plot
+    pub fn name(&self) -> &'static str {
+        match self {
+            Ecosystem::Cargo => "cargo",
+            Ecosystem::Npm => "npm",
+            Ecosystem::Python => "python",
+        }
+    }
+}
+
+/// An external package that is used directly by the repository
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dependency {
+    pub name: String,
+    /// The locked version
+    pub version: String,
+    pub ecosystem: Ecosystem,
+    /// The folders (relative to the scanned folder, empty for the root) of the packages that
+    /// use this package
+    pub users: Vec<String>,
+}
+
+/// Read the external packages that are used directly from the `Cargo.lock`,
+/// `package-lock.json` (version 2 or later) and `poetry.lock` in the given folder. Crates are
+/// linked to their folder in the workspace, npm workspaces to their folder, and all others
+/// to the folder itself.
+pub fn get_dependencies(path: &Path) -> Result<Vec<Dependency>> {
+    let mut dependencies = Vec::new();
+    if let Some(lock) = read_toml(&path.join("Cargo.lock"))? {
+        dependencies.extend(cargo_dependencies(path, &lock)?);
+    }
+    let npm_lock = path.join("package-lock.json");
+    if let Some(text) = read(&npm_lock)? {
+        let lock = serde_json::from_str::<Json>(&text).map_err(|e| Error::InvalidManifest {
+            path: npm_lock,
+            message: e.to_string(),
+        })?;
+        dependencies.extend(npm_dependencies(&lock));
+    }
+    if let Some(lock) = read_toml(&path.join("poetry.lock"))? {
+        let project = read_toml(&path.join("pyproject.toml"))?;
+        dependencies.extend(poetry_dependencies(&lock, project.as_ref()));
+    }
+    Ok(dependencies)
+}
+
+/// Read a file, `None` if it does not exist
+fn read(path: &Path) -> Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(Some(text)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(Error::io(path, e)),
+    }
+}
+
+fn read_toml(path: &Path) -> Result<Option<Table>> {
+    match read(path)? {
+        Some(text) => text
+            .parse::<Table>()
+            .map(Some)
+            .map_err(|e| Error::InvalidManifest {
+                path: path.to_path_buf(),
+                message: e.message().to_string(),
+            }),
+        None => Ok(None),
+    }
+}
+
+/// Get the direct dependencies of the local crates (the ones without a source)
+fn cargo_dependencies(path: &Path, lock: &Table) -> Result<Vec<Dependency>> {
+    let folders = get_workspace(path)?
+        .packages
+        .into_iter()
+        .map(|p| (p.name, p.path))
+        .collect::<HashMap<_, _>>();
+    let packages = lock
+        .get("package")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_table)
+        .collect::<Vec<_>>();
+    let field = |package: &Table, key: &str| {
+        package
+            .get(key)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    let mut dependencies: Vec<Dependency> = Vec::new();
+    for package in packages.iter().filter(|p| !p.contains_key("source")) {
+        let folder = folders
+            .get(&field(package, "name"))
+            .cloned()
+            .unwrap_or_default();
+        let used = package
+            .get("dependencies")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str);
+        for used in used {
+            // Either `name` or `name version` when multiple versions are locked
+            let mut parts = used.split(' ');
+            let (name, version) = (parts.next().unwrap_or_default(), parts.next());
+            let external = packages.iter().find(|p| {
+                p.contains_key("source")
+                    && field(p, "name") == name
+                    && version.is_none_or(|v| field(p, "version") == v)
+            });
+            if let Some(external) = external {
+                add_user(
+                    &mut dependencies,
+                    name,
+                    &field(external, "version"),
+                    Ecosystem::Cargo,
+                    &folder,
+                );
+            }
+        }
+    }
+    Ok(dependencies)
+}
+
+/// Get the direct dependencies of the root package and all workspaces in a lock file with a
+/// `packages` map (version 2 or later)
+fn npm_dependencies(lock: &Json) -> Vec<Dependency> {
+    let packages = match lock.get("packages").and_then(Json::as_object) {
+        Some(packages) => packages,
+        None => return Vec::new(),
+    };
+    let mut dependencies = Vec::new();
+    for (folder, package) in packages {
+        if folder.starts_with("node_modules/") || folder.contains("/node_modules/") {
+            continue;
+        }
+        let used = [
+            "dependencies",
+            "devDependencies",
+            "optionalDependencies",
+            "peerDependencies",
+        ]
+        .iter()
+        .filter_map(|key| package.get(*key).and_then(Json::as_object))
+        .flat_map(|d| d.keys());
+        for name in used {
+            // Packages are installed next to the workspace or hoisted to the root
+            let installed = [
+                format!("{}/node_modules/{}", folder, name),
+                format!("node_modules/{}", name),
+            ]
+            .into_iter()
+            .find_map(|key| packages.get(&key));
+            if let Some(installed) = installed {
+                if installed.get("link").and_then(Json::as_bool) == Some(true) {
+                    continue;
+                }
+                let version = installed
+                    .get("version")
+                    .and_then(Json::as_str)
+                    .unwrap_or_default();
+                add_user(&mut dependencies, name, version, Ecosystem::Npm, folder);
+            }
+        }
+    }
+    dependencies
+}
+
+/// Get the packages from a poetry lock file, only the ones listed in `pyproject.toml` when
+/// it is given
+fn poetry_dependencies(lock: &Table, project: Option<&Table>) -> Vec<Dependency> {
+    let direct = project.map(python_requirements);
+    let mut dependencies = Vec::new();
+    let packages = lock
+        .get("package")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_table);
+    for package in packages {
+        let name = package
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        if direct
+            .as_ref()
+            .is_none_or(|d| d.contains(&normalize_python(name)))
+        {
+            let version = package
+                .get("version")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            add_user(&mut dependencies, name, version, Ecosystem::Python, "");
+        }
+    }
+    dependencies
+}
+
+/// The normalized names of the packages required in a `pyproject.toml`, for both poetry and
+/// standard (PEP 621) projects
+fn python_requirements(project: &Table) -> Vec<String> {
+    let mut names = Vec::new();
+    let poetry = project
+        .get("tool")
+        .and_then(|t| t.get("poetry"))
+        .and_then(Value::as_table);
+    if let Some(poetry) = poetry {
+        let groups = poetry
+            .get("group")
+            .and_then(Value::as_table)
+            .into_iter()
+            .flat_map(|g| g.values())
+            .filter_map(|g| g.get("dependencies"));
+        let tables = ["dependencies", "dev-dependencies"]
+            .iter()
+            .filter_map(|key| poetry.get(*key))
+            .chain(groups)
+            .filter_map(Value::as_table);
+        for table in tables {
+            names.extend(
+                table
+                    .keys()
+                    .filter(|k| *k != "python")
+                    .map(|k| normalize_python(k)),
+            );
+        }
+    }
+    let project = project.get("project").and_then(Value::as_table);
+    let optional = project
+        .and_then(|p| p.get("optional-dependencies"))
+        .and_then(Value::as_table)
+        .into_iter()
+        .flat_map(|o| o.values());
+    let requirements = project
+        .and_then(|p| p.get("dependencies"))
+        .into_iter()
+        .chain(optional)
+        .filter_map(Value::as_array)
+        .flatten()
+        .filter_map(Value::as_str);
+    for requirement in requirements {
+        // The name ends at the first version specifier, extra, marker or space
+        let end = requirement
+            .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_' || c == '.'))
+            .unwrap_or(requirement.len());
+        names.push(normalize_python(&requirement[..end]));
+    }
+    names
+}
+
+/// Normalize a Python package name, which is case insensitive and treats `-`, `_` and `.`
+/// the same
+fn normalize_python(name: &str) -> String {
+    name.to_lowercase().replace(['_', '.'], "-")
+}
+
+/// Add a folder as user of a package, adding the package if it is new
+fn add_user(
+    dependencies: &mut Vec<Dependency>,
+    name: &str,
+    version: &str,
+    ecosystem: Ecosystem,
+    folder: &str,
+) {
+    let index = dependencies
+        .iter()
+        .position(|d| d.ecosystem == ecosystem && d.name == name && d.version == version)
+        .unwrap_or_else(|| {
+            dependencies.push(Dependency {
+                name: name.to_string(),
+                version: version.to_string(),
+                ecosystem,
+                users: Vec::new(),
+            });
+            dependencies.len() - 1
+        });
+    let users = &mut dependencies[index].users;
+    if !users.iter().any(|u| u == folder) {
+        users.push(folder.to_string());
+    }
+}
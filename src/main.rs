@@ -1,27 +1,411 @@
-#![allow(dead_code)]
-mod plot;
-mod structs;
-mod structure;
-use plot::plot;
-use structure::*;
-
-use std::io::{stdin, stdout, Write};
-use std::path::Path;
-
-fn main() {
-    let structure = get_structure(Path::new("../git-cuttle"), &[".vscode", "target", ".git"]);
-    println!("{:?}", structure);
-    if let Some(item) = structure {
-        plot(item, "plot.svg");
+use git_cuttle::{
+    get_dependencies, get_diff_structure, get_history, get_patch_structure, get_revision_structure,
+    get_status, get_structure, get_timeline, get_workspace, parse_range, plot, plot_timeline,
+    save_hotspots, CExtractor, ColourMode, CouplingOptions, Error, Format, FrameStep, PlotOptions,
+    ScanOptions, SizeMode,
+};
+
+use clap::{ArgAction, Parser, ValueEnum};
+use std::error::Error as _;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+/// Exit code when the output could not be written
+const EXIT_WRITE_FAILURE: u8 = 1;
+/// Exit code when a given path could not be read or contained nothing to plot
+const EXIT_NOTHING_TO_PLOT: u8 = 3;
+
+/// Visualise the structure of a repository as a zoomable circle diagram
+#[derive(Parser, Debug)]
+#[command(name = "git-cuttle", version, about)]
+struct Args {
+    /// The repositories (or any other folders) to visualise
+    #[arg(value_name = "PATH", default_value = ".")]
+    paths: Vec<PathBuf>,
+    /// The file to save the plot to, defaults to `plot.svg` or `plot.html` depending on the format.
+    /// When multiple paths are given the name of each folder is appended to the file name.
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+    /// Glob pattern of files and folders to leave out, can be given multiple times (replaces
+    /// the defaults). Patterns without a `/` match at any depth, others are matched against
+    /// the path relative to the scanned folder. Patterns starting with `!` include paths again.
+    #[arg(short, long, value_name = "PATTERN", default_values = [".git", "target", ".vscode"])]
+    ignore: Vec<String>,
+    /// Glob pattern of files to show, can be given multiple times. When given all other files
+    /// are left out, patterns starting with `!` leave out matching files.
+    #[arg(long, value_name = "PATTERN")]
+    include: Vec<String>,
+    /// The output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Svg)]
+    format: OutputFormat,
+    /// Show the files of the given git revision (commit, tag, branch) instead of the working
+    /// directory, without checking it out
+    #[arg(long, value_name = "REV", conflicts_with = "diff")]
+    rev: Option<String>,
+    /// Show the files of the newer revision of a range like `main..feature` and mark the files
+    /// that were added, deleted, modified or renamed since the older revision. A single
+    /// revision is compared to `HEAD`, `a...b` compares to the common ancestor of both.
+    #[arg(long, value_name = "RANGE")]
+    diff: Option<String>,
+    /// Mark the files touched by a unified diff (like a `.patch` file) with the number of lines
+    /// added and removed, on top of the working directory or `--rev`
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["diff", "timeline"])]
+    patch: Option<PathBuf>,
+    /// Save an animated HTML page of the history up to `--rev` instead, with a frame for every
+    /// `commit`, every N commits (a number), or the last commit of every `day`, `week` (starting
+    /// on Monday) or calendar `month`, in UTC
+    #[arg(long, value_name = "STEP", value_parser = parse_step, conflicts_with = "diff")]
+    timeline: Option<FrameStep>,
+    /// Mark the files that are modified, staged, untracked or conflicted in the working tree
+    #[arg(long, conflicts_with_all = ["rev", "diff", "timeline"])]
+    status: bool,
+    /// Also show the files and folders that are ignored by git
+    #[arg(long)]
+    no_gitignore: bool,
+    /// What the colour of the files shows, all but `type` use the git history
+    #[arg(short, long, value_enum, default_value_t = Colour::Type, conflicts_with = "timeline")]
+    colour: Colour,
+    /// What the size of the files shows, `churn` uses the git history
+    #[arg(short, long, value_enum, default_value_t = Size::Bytes, conflicts_with = "timeline")]
+    size: Size,
+    /// Draw lines between files changed together in at least this many commits
+    #[arg(long, value_name = "COMMITS", conflicts_with = "timeline")]
+    coupling: Option<u64>,
+    /// The minimal strength of the coupling between files to draw a line, the number of shared
+    /// commits divided by the average number of commits of both files
+    #[arg(long, value_name = "FRACTION", default_value_t = CouplingOptions::default().min_strength, requires = "coupling")]
+    coupling_strength: f64,
+    /// Highlight the crates of the Cargo workspace in the folder and draw their dependencies
+    /// on each other, the manifests are read from the working directory
+    #[arg(long, conflicts_with = "timeline")]
+    crates: bool,
+    /// Show the external packages from `Cargo.lock`, `package-lock.json` and `poetry.lock` in a
+    /// ring around the folder, with lines from the folders that use them
+    #[arg(long, conflicts_with = "timeline")]
+    dependencies: bool,
+    /// A folder to look for files included by C and C++ sources in, relative to the scanned
+    /// folder, can be given more than once
+    #[arg(long, value_name = "DIR")]
+    include_dir: Vec<String>,
+    /// The compilation database to read more include folders from, relative to the scanned
+    /// folder
+    #[arg(long, value_name = "FILE", default_value = "compile_commands.json")]
+    compile_commands: String,
+    /// Also save a Markdown table of the given number of files with the highest hotspot score
+    /// (commits times lines) next to the plot, as `<output>-hotspots.md`
+    #[arg(long, value_name = "COUNT", conflicts_with = "timeline")]
+    hotspots: Option<usize>,
+    /// Do not print anything except errors
+    #[arg(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+    /// Print more information, give twice to also print the scanned structure
+    #[arg(short, long, action = ArgAction::Count)]
+    verbose: u8,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum OutputFormat {
+    /// A standalone interactive SVG image
+    Svg,
+    /// An HTML page with the interactive SVG image embedded
+    Html,
+}
+
+impl From<OutputFormat> for Format {
+    fn from(format: OutputFormat) -> Self {
+        match format {
+            OutputFormat::Svg => Format::Svg,
+            OutputFormat::Html => Format::Html,
+        }
     }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum Colour {
+    /// The kind of file: code, data, configuration or unknown
+    Type,
+    /// The number of commits that changed the file
+    Commits,
+    /// The number of lines added and removed over the history of the file
+    Churn,
+    /// The time the file was last changed
+    Age,
+    /// The time the file was added
+    Created,
+    /// The author with the most commits to the file
+    Author,
+    /// The number of commits times the number of lines of the file
+    Hotspot,
+}
+
+impl From<Colour> for ColourMode {
+    fn from(colour: Colour) -> Self {
+        match colour {
+            Colour::Type => ColourMode::Type,
+            Colour::Commits => ColourMode::Commits,
+            Colour::Churn => ColourMode::Churn,
+            Colour::Age => ColourMode::Age,
+            Colour::Created => ColourMode::Created,
+            Colour::Author => ColourMode::Author,
+            Colour::Hotspot => ColourMode::Hotspot,
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum Size {
+    /// The size of the file in bytes
+    Bytes,
+    /// The number of lines added and removed over the history of the file
+    Churn,
+}
+
+impl From<Size> for SizeMode {
+    fn from(size: Size) -> Self {
+        match size {
+            Size::Bytes => SizeMode::Bytes,
+            Size::Churn => SizeMode::Churn,
+        }
+    }
+}
+
+/// Parse the step between the frames of a timeline
+fn parse_step(step: &str) -> Result<FrameStep, String> {
+    match step {
+        "commit" => Ok(FrameStep::Commits(1)),
+        "day" => Ok(FrameStep::Days(1)),
+        "week" => Ok(FrameStep::Week),
+        "month" => Ok(FrameStep::Month),
+        n => match n.parse::<usize>() {
+            Ok(n) if n > 0 => Ok(FrameStep::Commits(n)),
+            _ => {
+                Err("expected `commit`, `day`, `week`, `month` or a number of commits".to_string())
+            }
+        },
+    }
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let mut options = ScanOptions::default();
+    options.ignore = args.ignore.clone();
+    options.include = args.include.clone();
+    options.git_ignore = !args.no_gitignore;
+    // Replaces the built in C extractor with the include folders of the arguments
+    options.extractors.register(CExtractor {
+        include_dirs: args.include_dir.clone(),
+        compile_commands: Some(args.compile_commands.clone()),
+    });
+    let mut plot_options = PlotOptions::default();
+    plot_options.colour = args.colour.into();
+    plot_options.size = args.size.into();
+    plot_options.coupling = args.coupling.map(|min_commits| {
+        let mut coupling = CouplingOptions::default();
+        coupling.min_commits = min_commits;
+        coupling.min_strength = args.coupling_strength;
+        coupling
+    });
+    let mut code = ExitCode::SUCCESS;
+
+    for path in &args.paths {
+        let path = match path.canonicalize() {
+            Ok(p) => p,
+            Err(e) => {
+                eprintln!("Could not read '{}': {}", path.display(), e);
+                code = ExitCode::from(EXIT_NOTHING_TO_PLOT);
+                continue;
+            }
+        };
+        if args.verbose > 0 {
+            println!("Scanning {}", path.display());
+        }
+        if let Some(step) = args.timeline {
+            let output = output_path(&args, &path);
+            let result = get_timeline(&path, args.rev.as_deref(), step, &options)
+                .map_err(|e| {
+                    (
+                        EXIT_NOTHING_TO_PLOT,
+                        format!("Could not read the timeline of '{}': {}", path.display(), e),
+                    )
+                })
+                .and_then(|timeline| {
+                    plot_timeline(&timeline, &output, &plot_options)
+                        .map(|_| timeline.frames.len())
+                        .map_err(|e| {
+                            (
+                                EXIT_WRITE_FAILURE,
+                                format!("Could not save '{}': {}", output.display(), reason(&e)),
+                            )
+                        })
+                });
+            match result {
+                Ok(frames) => {
+                    if !args.quiet {
+                        println!("Saved {} ({} frames)", output.display(), frames);
+                    }
+                }
+                Err((exit, message)) => {
+                    eprintln!("{}", message);
+                    code = ExitCode::from(exit);
+                }
+            }
+            continue;
+        }
+        let mut revision = args.rev.clone();
+        let scan = if let Some(range) = &args.diff {
+            parse_range(&path, range).and_then(|(old, new)| {
+                let scan = get_diff_structure(&path, &old, &new, &options);
+                revision = Some(new);
+                scan
+            })
+        } else if let Some(patch) = &args.patch {
+            get_patch_structure(&path, patch, args.rev.as_deref(), &options)
+        } else if let Some(rev) = &args.rev {
+            get_revision_structure(&path, rev, &options)
+        } else {
+            get_structure(&path, &options)
+        };
+        let mut structure = match scan {
+            Ok(scan) => {
+                if !args.quiet {
+                    for warning in &scan.warnings {
+                        eprintln!("warning: {}", warning);
+                    }
+                }
+                scan.root
+            }
+            Err(e) => {
+                eprintln!("Could not scan '{}': {}", path.display(), e);
+                code = ExitCode::from(EXIT_NOTHING_TO_PLOT);
+                continue;
+            }
+        };
+        if plot_options.needs_history() || args.hotspots.is_some() {
+            if args.verbose > 0 {
+                println!("Reading the git history of {}", path.display());
+            }
+            match get_history(&path, revision.as_deref()) {
+                Ok(history) => structure.add_history(&history),
+                Err(e) => {
+                    eprintln!("Could not read the history of '{}': {}", path.display(), e);
+                    code = ExitCode::from(EXIT_NOTHING_TO_PLOT);
+                    continue;
+                }
+            }
+        }
+        if args.status {
+            match get_status(&path) {
+                Ok(statuses) => structure.add_status(&statuses),
+                Err(e) => {
+                    eprintln!("Could not read the status of '{}': {}", path.display(), e);
+                    code = ExitCode::from(EXIT_NOTHING_TO_PLOT);
+                    continue;
+                }
+            }
+        }
+        if args.crates {
+            match get_workspace(&path) {
+                Ok(workspace) => structure.add_workspace(&workspace),
+                Err(e) => {
+                    eprintln!(
+                        "Could not read the workspace of '{}': {}",
+                        path.display(),
+                        e
+                    );
+                    code = ExitCode::from(EXIT_NOTHING_TO_PLOT);
+                    continue;
+                }
+            }
+        }
+        if args.dependencies {
+            match get_dependencies(&path) {
+                Ok(dependencies) => structure.add_dependencies(&dependencies),
+                Err(e) => {
+                    eprintln!(
+                        "Could not read the dependencies of '{}': {}",
+                        path.display(),
+                        e
+                    );
+                    code = ExitCode::from(EXIT_NOTHING_TO_PLOT);
+                    continue;
+                }
+            }
+        }
+        if args.verbose > 1 {
+            println!("{:?}", structure);
+        }
+
+        let files = structure.files();
+        if files == 0 {
+            eprintln!("Nothing to plot in '{}'", path.display());
+            code = ExitCode::from(EXIT_NOTHING_TO_PLOT);
+            continue;
+        }
+        let output = output_path(&args, &path);
+        match plot(&structure, &output, args.format.into(), &plot_options) {
+            Ok(()) => {
+                if !args.quiet {
+                    println!("Saved {} ({} files)", output.display(), files);
+                }
+            }
+            Err(e) => {
+                eprintln!("Could not save '{}': {}", output.display(), reason(&e));
+                code = ExitCode::from(EXIT_WRITE_FAILURE);
+            }
+        }
+        if let Some(count) = args.hotspots {
+            let stem = output
+                .file_stem()
+                .map_or("plot".to_string(), |s| s.to_string_lossy().to_string());
+            let table = output.with_file_name(format!("{}-hotspots.md", stem));
+            match save_hotspots(&structure, &table, count) {
+                Ok(()) => {
+                    if !args.quiet {
+                        println!("Saved {}", table.display());
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Could not save '{}': {}", table.display(), reason(&e));
+                    code = ExitCode::from(EXIT_WRITE_FAILURE);
+                }
+            }
+        }
+    }
+    code
+}
+
+/// The reason of an error without the path it is about, for messages that give the path
+fn reason(error: &Error) -> String {
+    match error.source() {
+        Some(source) => source.to_string(),
+        None => error.to_string(),
+    }
+}
 
-    let mut s = String::new();
-    print!(">");
-    let _ = stdout().flush();
-    stdin().read_line(&mut s).expect("Incorrect input");
-    let structure = get_structure(Path::new("../pdbtbx"), &[".vscode", "target", ".git"]);
-    println!("{:?}", structure);
-    if let Some(item) = structure {
-        plot(item, "plot.svg");
+/// Get the path to save the plot for the given repository to
+fn output_path(args: &Args, repository: &Path) -> PathBuf {
+    let extension = match args.format {
+        _ if args.timeline.is_some() => "html",
+        OutputFormat::Svg => "svg",
+        OutputFormat::Html => "html",
+    };
+    let output = args
+        .output
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(format!("plot.{}", extension)));
+    if args.paths.len() > 1 {
+        let stem = output
+            .file_stem()
+            .map_or("plot".to_string(), |s| s.to_string_lossy().to_string());
+        let name = repository
+            .file_name()
+            .map_or("root".to_string(), |s| s.to_string_lossy().to_string());
+        let file_name = match output.extension() {
+            Some(ext) => format!("{}-{}.{}", stem, name, ext.to_string_lossy()),
+            None => format!("{}-{}", stem, name),
+        };
+        output.with_file_name(file_name)
+    } else {
+        output
     }
 }
@@ -0,0 +1,479 @@
+use crate::diff::Change;
+use crate::error::*;
+use crate::filter::PathFilter;
+use crate::structure::*;
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// The changes to a single file in a unified diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FilePatch {
+    /// The path in the base tree, or the new path for added and renamed files
+    pub path: String,
+    /// How the file changed, renamed files give [`Change::Renamed`] with their old path
+    pub change: Change,
+    /// The number of added lines
+    pub lines_added: u64,
+    /// The number of removed lines
+    pub lines_removed: u64,
+    /// The number of bytes in the added lines minus the bytes in the removed lines
+    pub size_delta: i64,
+    /// The new path of a modified file when it differs from the old one, which happens in
+    /// patches without git headers like `diff -u a.c.orig a.c`
+    pub other_path: Option<String>,
+}
+
+/// What the headers of the current file in a patch say about it
+#[derive(Debug, Default)]
+struct Header {
+    old: Option<String>,
+    new: Option<String>,
+    added: bool,
+    deleted: bool,
+    renamed: bool,
+    /// A [`FilePatch`] is already made for this file
+    done: bool,
+}
+
+impl Header {
+    /// Make the [`FilePatch`] for the file, `None` if it has no path or was already made
+    fn finish(&mut self) -> Option<FilePatch> {
+        if self.done {
+            return None;
+        }
+        let mut other_path = None;
+        let (path, change) = match (self.old.clone(), self.new.clone()) {
+            (_, Some(new)) if self.added => (new, Change::Added),
+            (Some(old), _) if self.deleted => (old, Change::Deleted),
+            (Some(old), Some(new)) if self.renamed && old != new => (
+                new,
+                Change::Renamed {
+                    from: old,
+                    size_delta: 0,
+                },
+            ),
+            (Some(old), new) => {
+                other_path = new.filter(|new| *new != old);
+                (old, Change::Modified { size_delta: 0 })
+            }
+            (None, Some(path)) => (path, Change::Modified { size_delta: 0 }),
+            (None, None) => return None,
+        };
+        self.done = true;
+        Some(FilePatch {
+            path,
+            change,
+            lines_added: 0,
+            lines_removed: 0,
+            size_delta: 0,
+            other_path,
+        })
+    }
+}
+
+/// Parse a unified diff, as made by `git diff` or `diff -u`. The first component of every
+/// path is left out (like `patch -p1`) when it is `a/` or `b/`. Files without any lines,
+/// like pure renames and binary files, are found from the git headers and the `Binary
+/// files ... differ` lines.
+pub fn parse_patch(text: &str) -> Vec<FilePatch> {
+    let mut files: Vec<FilePatch> = Vec::new();
+    // The number of lines left in the current hunk for the old and new file
+    let mut hunk = (0, 0);
+    let mut header = Header::default();
+    for line in text.lines() {
+        if hunk != (0, 0) {
+            let file = match files.last_mut() {
+                Some(file) => file,
+                None => break,
+            };
+            // The length of the line with the sign instead of the line break
+            let size = line.len() as i64;
+            match line.chars().next() {
+                Some('+') => {
+                    hunk.1 -= 1;
+                    file.lines_added += 1;
+                    file.size_delta += size;
+                }
+                Some('-') => {
+                    hunk.0 -= 1;
+                    file.lines_removed += 1;
+                    file.size_delta -= size;
+                }
+                Some('\\') => {}
+                _ => {
+                    hunk.0 -= 1;
+                    hunk.1 -= 1;
+                }
+            }
+            hunk = (hunk.0.max(0), hunk.1.max(0));
+        } else if let Some(rest) = line.strip_prefix("diff ") {
+            files.extend(header.finish());
+            header = Header::default();
+            // `diff --git a/old b/new`, only used when there are no other headers
+            if let Some((old, new)) = rest
+                .strip_prefix("--git a/")
+                .and_then(|paths| paths.split_once(" b/"))
+            {
+                header.old = Some(old.to_string());
+                header.new = Some(new.to_string());
+            }
+        } else if line.starts_with("new file mode") {
+            header.added = true;
+        } else if line.starts_with("deleted file mode") {
+            header.deleted = true;
+        } else if let Some(path) = line.strip_prefix("rename from ") {
+            header.old = Some(path.to_string());
+            header.renamed = true;
+        } else if let Some(path) = line.strip_prefix("rename to ") {
+            header.new = Some(path.to_string());
+            header.renamed = true;
+        } else if let Some(path) = line.strip_prefix("--- ") {
+            match patch_path(path) {
+                Some(path) => header.old = Some(path),
+                None => header.added = true,
+            }
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            match patch_path(path) {
+                Some(path) => header.new = Some(path),
+                None => header.deleted = true,
+            }
+            files.extend(header.finish());
+        } else if let Some(paths) = line
+            .strip_prefix("Binary files ")
+            .and_then(|l| l.strip_suffix(" differ"))
+        {
+            if let Some((old, new)) = paths.split_once(" and ") {
+                match patch_path(old) {
+                    Some(path) => header.old = Some(path),
+                    None => header.added = true,
+                }
+                match patch_path(new) {
+                    Some(path) => header.new = Some(path),
+                    None => header.deleted = true,
+                }
+            }
+            files.extend(header.finish());
+        } else if let Some(range) = line.strip_prefix("@@ ") {
+            hunk = parse_hunk(range).unwrap_or((0, 0));
+        }
+    }
+    files.extend(header.finish());
+    for file in &mut files {
+        match &mut file.change {
+            Change::Modified { size_delta } | Change::Renamed { size_delta, .. } => {
+                *size_delta = file.size_delta
+            }
+            _ => {}
+        }
+    }
+    files
+}
+
+/// Get the path from a `---` or `+++` line, `None` for `/dev/null`
+fn patch_path(path: &str) -> Option<String> {
+    // `diff -u` adds the modification time after a tab
+    let path = path.split('\t').next().unwrap_or_default().trim_end();
+    let path = path
+        .strip_prefix('"')
+        .and_then(|p| p.strip_suffix('"'))
+        .unwrap_or(path);
+    if path == "/dev/null" {
+        None
+    } else {
+        let path = path
+            .strip_prefix("a/")
+            .or_else(|| path.strip_prefix("b/"))
+            .unwrap_or(path);
+        Some(path.to_string())
+    }
+}
+
+/// Get the number of old and new lines from a hunk header like `-1,5 +1,7 @@`
+fn parse_hunk(range: &str) -> Option<(i64, i64)> {
+    let mut parts = range.split_whitespace();
+    let count = |part: Option<&str>, sign: char| -> Option<i64> {
+        let part = part?.strip_prefix(sign)?;
+        match part.split_once(',') {
+            Some((_, count)) => count.parse().ok(),
+            None => Some(1),
+        }
+    };
+    Some((count(parts.next(), '-')?, count(parts.next(), '+')?))
+}
+
+/// Scan the base tree of a patch, the working directory or the given revision, and mark every
+/// file the patch touches with its change and the number of lines added and removed. Files
+/// added by the patch that are not in the base tree yet are added with the size of their
+/// lines. Renamed files are marked at their new path, or at their old path when the base
+/// tree does not have the patch applied. A modified file whose old path is not in the base
+/// tree is looked for at its new path and at both paths without their first folder. Files
+/// that are not in the base tree give a warning.
+pub fn get_patch_structure(
+    path: &Path,
+    patch: &Path,
+    revision: Option<&str>,
+    options: &ScanOptions,
+) -> Result<Scan> {
+    let text = fs::read(patch).map_err(|e| Error::io(patch, e))?;
+    let mut scan = match revision {
+        Some(revision) => get_revision_structure(path, revision, options)?,
+        None => get_structure(path, options)?,
+    };
+    let ignore = PathFilter::new(&options.ignore)?;
+    let include = PathFilter::new(&options.include)?;
+    let mut files = HashMap::new();
+    for mut file in parse_patch(&String::from_utf8_lossy(&text)) {
+        if let Some(path) = find_modified(&file, &scan.root) {
+            file.path = path;
+        }
+        if !is_shown(&file.path, &ignore, &include) {
+            continue;
+        }
+        let exists = scan.root.get(&file.path).is_some();
+        match &file.change {
+            Change::Added if !exists => {
+                let size = file.size_delta.max(0) as u64;
+                if let Some(item) = scan.root.insert_file(&file.path, size, Vec::new()) {
+                    item.set_lines(file.lines_added, file.lines_removed);
+                    item.set_change(Change::Added);
+                }
+            }
+            Change::Renamed { from, .. } if !exists => {
+                files.insert(from.clone(), file);
+            }
+            _ => {
+                files.insert(file.path.clone(), file);
+            }
+        }
+    }
+    scan.root.add_patch(&mut files);
+    for path in files.into_keys() {
+        scan.warnings
+            .push(Warning::new(Path::new(&path), WarningKind::NotFound));
+    }
+    Ok(scan)
+}
+
+/// Find the file a modified file in a patch refers to when its path is not in the scanned
+/// tree: its other path or, like `patch -p1`, either path without the first folder
+fn find_modified(file: &FilePatch, root: &Item) -> Option<String> {
+    if !matches!(file.change, Change::Modified { .. }) || root.get(&file.path).is_some() {
+        return None;
+    }
+    let paths = std::iter::once(&file.path).chain(&file.other_path);
+    let stripped = paths
+        .clone()
+        .filter_map(|path| path.split_once('/').map(|(_, rest)| rest.to_string()));
+    file.other_path
+        .iter()
+        .cloned()
+        .chain(stripped)
+        .find(|path| root.get(path).is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, change: Change, lines: (u64, u64), size_delta: i64) -> FilePatch {
+        FilePatch {
+            path: path.to_string(),
+            change,
+            lines_added: lines.0,
+            lines_removed: lines.1,
+            size_delta,
+            other_path: None,
+        }
+    }
+
+    #[test]
+    fn patch_path_strips_prefixes_and_dev_null() {
+        assert_eq!(patch_path("a/src/lib.rs"), Some("src/lib.rs".to_string()));
+        assert_eq!(patch_path("b/src/lib.rs"), Some("src/lib.rs".to_string()));
+        assert_eq!(
+            patch_path("src/lib.rs\t2024-01-01 10:00"),
+            Some("src/lib.rs".to_string())
+        );
+        assert_eq!(
+            patch_path("\"a/with space.txt\""),
+            Some("with space.txt".to_string())
+        );
+        assert_eq!(patch_path("/dev/null"), None);
+    }
+
+    #[test]
+    fn parse_hunk_counts_lines() {
+        assert_eq!(parse_hunk("-1,5 +1,7 @@ fn main()"), Some((5, 7)));
+        assert_eq!(parse_hunk("-3 +3 @@"), Some((1, 1)));
+        assert_eq!(parse_hunk("-0,0 +1,2 @@"), Some((0, 2)));
+        assert_eq!(parse_hunk("garbage"), None);
+    }
+
+    #[test]
+    fn modified_file() {
+        let patch = concat!(
+            "diff --git a/a.txt b/a.txt\n",
+            "--- a/a.txt\n",
+            "+++ b/a.txt\n",
+            "@@ -1,2 +1,2 @@\n",
+            " one\n",
+            "-two\n",
+            "+three\n",
+        );
+        assert_eq!(
+            parse_patch(patch),
+            vec![file("a.txt", Change::Modified { size_delta: 2 }, (1, 1), 2)]
+        );
+    }
+
+    #[test]
+    fn added_and_deleted_files() {
+        let patch = concat!(
+            "diff --git a/new.txt b/new.txt\n",
+            "new file mode 100644\n",
+            "--- /dev/null\n",
+            "+++ b/new.txt\n",
+            "@@ -0,0 +1 @@\n",
+            "+hello\n",
+            "diff --git a/old.txt b/old.txt\n",
+            "deleted file mode 100644\n",
+            "--- a/old.txt\n",
+            "+++ /dev/null\n",
+            "@@ -1 +0,0 @@\n",
+            "-bye\n",
+        );
+        assert_eq!(
+            parse_patch(patch),
+            vec![
+                file("new.txt", Change::Added, (1, 0), 6),
+                file("old.txt", Change::Deleted, (0, 1), -4),
+            ]
+        );
+    }
+
+    #[test]
+    fn renamed_files() {
+        let patch = concat!(
+            "diff --git a/old.rs b/new.rs\n",
+            "similarity index 100%\n",
+            "rename from old.rs\n",
+            "rename to new.rs\n",
+            "diff --git a/x.rs b/y.rs\n",
+            "similarity index 90%\n",
+            "rename from x.rs\n",
+            "rename to y.rs\n",
+            "--- a/x.rs\n",
+            "+++ b/y.rs\n",
+            "@@ -1 +1 @@\n",
+            "-a\n",
+            "+bb\n",
+        );
+        let renamed = |from: &str, size_delta| Change::Renamed {
+            from: from.to_string(),
+            size_delta,
+        };
+        assert_eq!(
+            parse_patch(patch),
+            vec![
+                file("new.rs", renamed("old.rs", 0), (0, 0), 0),
+                file("y.rs", renamed("x.rs", 1), (1, 1), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn binary_files() {
+        let patch = concat!(
+            "diff --git a/logo.png b/logo.png\n",
+            "index 1234..5678 100644\n",
+            "Binary files a/logo.png and b/logo.png differ\n",
+            "diff --git a/icon.png b/icon.png\n",
+            "new file mode 100644\n",
+            "Binary files /dev/null and b/icon.png differ\n",
+        );
+        assert_eq!(
+            parse_patch(patch),
+            vec![
+                file("logo.png", Change::Modified { size_delta: 0 }, (0, 0), 0),
+                file("icon.png", Change::Added, (0, 0), 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn git_binary_patch_and_mode_change() {
+        let patch = concat!(
+            "diff --git a/data.bin b/data.bin\n",
+            "index 1234..5678\n",
+            "GIT binary patch\n",
+            "literal 3\n",
+            "KcmZ?d00001\n",
+            "\n",
+            "diff --git a/run.sh b/run.sh\n",
+            "old mode 100644\n",
+            "new mode 100755\n",
+        );
+        assert_eq!(
+            parse_patch(patch),
+            vec![
+                file("data.bin", Change::Modified { size_delta: 0 }, (0, 0), 0),
+                file("run.sh", Change::Modified { size_delta: 0 }, (0, 0), 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn plain_diff_without_git_headers() {
+        let patch = concat!(
+            "--- orig/a.c\t2024-01-01\n",
+            "+++ a.c\t2024-01-02\n",
+            "@@ -1 +1,2 @@\n",
+            " x\n",
+            "+y\n",
+        );
+        assert_eq!(
+            parse_patch(patch),
+            vec![FilePatch {
+                other_path: Some("a.c".to_string()),
+                ..file("orig/a.c", Change::Modified { size_delta: 2 }, (1, 0), 2)
+            }]
+        );
+    }
+
+    #[test]
+    fn modified_files_are_found_by_their_other_paths() {
+        let mut root = Item::Folder {
+            name: "root".to_string(),
+            items: Vec::new(),
+            package: None,
+            dependencies: Vec::new(),
+        };
+        for path in ["a.c", "src/b.c", "c.c.orig"] {
+            root.insert_file(path, 1, Vec::new());
+        }
+        let modified = |old: &str, new: &str| FilePatch {
+            other_path: Some(new.to_string()),
+            ..file(old, Change::Modified { size_delta: 0 }, (0, 0), 0)
+        };
+        // `diff -u orig/a.c a.c` and `diff -u a.c.orig a.c`
+        assert_eq!(
+            find_modified(&modified("orig/a.c", "a.c"), &root),
+            Some("a.c".to_string())
+        );
+        assert_eq!(
+            find_modified(&modified("a.c.orig", "a.c"), &root),
+            Some("a.c".to_string())
+        );
+        // `diff -ur old/src/b.c new/src/b.c`
+        assert_eq!(
+            find_modified(&modified("old/src/b.c", "new/src/b.c"), &root),
+            Some("src/b.c".to_string())
+        );
+        // The old path is used when it exists
+        assert_eq!(find_modified(&modified("c.c.orig", "c.c"), &root), None);
+        assert_eq!(find_modified(&modified("x.c", "y.c"), &root), None);
+    }
+}
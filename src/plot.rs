@@ -1,31 +1,159 @@
+use crate::colour::*;
+use crate::diff::Change;
+use crate::error::*;
+use crate::history::format_date;
+use crate::lockfile::Dependency;
+use crate::status::Status;
 use crate::structs::*;
 use crate::structure::*;
+use crate::timeline::Timeline;
 
 use rand::seq::SliceRandom;
 use rand::thread_rng;
+use std::fs;
+use std::path::Path;
 use svg::node::element::*;
 use svg::Document;
 
 const MARGIN: f64 = 5.0;
+/// The width and height of the square the structure is laid out in
+const SIZE: f64 = 1024.0;
+/// The distance between the edge of the root and the ring of external packages
+const SATELLITE_DISTANCE: f64 = 60.0;
+/// The radius of an external package
+const SATELLITE_RADIUS: f64 = 8.0;
 
-pub fn plot(item: Item, path: &str) {
-    let size = 1024.0;
-    let margin = 20.0;
+/// The file formats a plot can be saved as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// A standalone SVG image
+    Svg,
+    /// An HTML page with the SVG image embedded
+    Html,
+}
+
+/// Options controlling what a plot shows, start from [`PlotOptions::default`]
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct PlotOptions {
+    /// What the colour of the files shows
+    pub colour: ColourMode,
+    /// What the size of the files shows
+    pub size: SizeMode,
+    /// Draw lines between files that are often changed in the same commits
+    pub coupling: Option<CouplingOptions>,
+}
+
+impl PlotOptions {
+    /// Check if the git history has to be added to the structure for these options
+    pub fn needs_history(&self) -> bool {
+        self.colour.needs_history() || self.size.needs_history() || self.coupling.is_some()
+    }
+}
+
+/// Which pairs of files that are changed in the same commits get a line, start from
+/// [`CouplingOptions::default`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct CouplingOptions {
+    /// The minimal number of commits that changed both files
+    pub min_commits: u64,
+    /// The minimal strength of the coupling, the number of shared commits divided by the
+    /// average number of commits of both files, from 0.0 to 1.0
+    pub min_strength: f64,
+}
+
+impl Default for CouplingOptions {
+    fn default() -> Self {
+        CouplingOptions {
+            min_commits: 3,
+            min_strength: 0.3,
+        }
+    }
+}
+
+/// Lay out, render and save the given structure in one go
+pub fn plot(item: &Item, path: &Path, format: Format, options: &PlotOptions) -> Result<()> {
+    save(
+        &render(&layout(item, options), options),
+        path,
+        format,
+        item.name(),
+    )
+}
 
+/// Lay out the given structure, placing every file and folder as a circle
+/// in a square of `SIZE` by `SIZE`.
+pub fn layout<'a>(item: &'a Item, options: &PlotOptions) -> EntityNode<'a> {
     let mut entities = plot_item(
-        &item,
-        Area::new(0.0, 0.0, size, size),
-        (item.files(), item.size()),
+        item,
+        Area::new(0.0, 0.0, SIZE, SIZE),
+        (item.files(), item.size_by(options.size)),
+        options.size,
     );
     improve_positions(&mut entities);
     entities = shrink_folder_sizes(entities);
     improve_positions(&mut entities);
-    entities = shrink_folder_sizes(entities);
-    println!("{:?}", entities);
-    let (plot, _) = plot_entities(&entities, Group::new().set("id", "view-root"), &entities);
+    shrink_folder_sizes(entities)
+}
+
+/// Render a laid out structure into an interactive SVG document
+pub fn render(layout: &EntityNode, options: &PlotOptions) -> Document {
+    let satellites = plot_dependencies(layout);
+    let margin = match satellites {
+        // Make room for the ring of external packages around the root
+        Some(_) => 20.0 + SATELLITE_DISTANCE + 2.0 * SATELLITE_RADIUS + 20.0,
+        None => 20.0,
+    };
+    let scheme = ColourScheme::new(options.colour, layout.files());
+    let mut legend_entries = scheme.legend();
+    if options.coupling.is_some() {
+        legend_entries.push((
+            "Changed together".to_string(),
+            "var(--color-cochange)".to_string(),
+        ));
+    }
+    if layout.files().iter().any(|f| f.change().is_some()) {
+        legend_entries.extend(Change::legend());
+    }
+    if layout.files().iter().any(|f| f.status().is_some()) {
+        legend_entries.extend(Status::legend());
+    }
+    if has_packages(layout) {
+        legend_entries.push((
+            "Crate dependency".to_string(),
+            "var(--color-crate)".to_string(),
+        ));
+    }
+    let mut ecosystems = layout_dependencies(layout)
+        .iter()
+        .map(|(_, d)| d.ecosystem)
+        .collect::<Vec<_>>();
+    ecosystems.sort();
+    ecosystems.dedup();
+    for ecosystem in ecosystems {
+        legend_entries.push((
+            format!("{} package", ecosystem.name()),
+            format!("var(--color-{})", ecosystem.name()),
+        ));
+    }
+    let legend = make_legend(&legend_entries, Point(SIZE - 160.0, 10.0));
+    let (mut plot, _) = plot_entities(
+        layout,
+        Group::new().set("id", "view-root"),
+        layout,
+        &scheme,
+        options,
+    );
+    if let Some(satellites) = satellites {
+        plot = plot.add(satellites);
+    }
 
-    let root = Document::new()
-        .set("viewBox", (-margin, -margin, size + margin, size + margin))
+    Document::new()
+        .set(
+            "viewBox",
+            (-margin, -margin, SIZE + 2.0 * margin, SIZE + 2.0 * margin),
+        )
         .set("xmlns:xlink", "http://www.w3.org/1999/xlink")
         .set("onload", "load()")
         .add(Style::new(std::include_str!("style.css")))
@@ -48,8 +176,120 @@ pub fn plot(item: Item, path: &str) {
             "toggle-references-button",
             Point(10.0, 90.0),
             "toggle_references_button()",
-        ));
-    svg::save(path, &root).unwrap();
+        ))
+        .add(legend)
+}
+
+/// Save a rendered document in the given format, the title is used for the HTML page
+pub fn save(document: &Document, path: &Path, format: Format, title: &str) -> Result<()> {
+    let result = match format {
+        Format::Svg => svg::save(path, document),
+        Format::Html => fs::write(path, html_page(title, &document.to_string())),
+    };
+    result.map_err(|e| Error::io(path, e))
+}
+
+/// Save a Markdown table of the given number of files with the highest hotspot score, the
+/// history has to be added to the structure first
+pub fn save_hotspots(item: &Item, path: &Path, count: usize) -> Result<()> {
+    let mut table = format!(
+        "# Hotspots of {}\n\n| Rank | File | Hotspot score | Commits | Lines |\n| ---: | --- | ---: | ---: | ---: |\n",
+        item.name()
+    );
+    for (rank, (file, history)) in item.hotspots(count).iter().enumerate() {
+        table += &format!(
+            "| {} | `{}` | {} | {} | {} |\n",
+            rank + 1,
+            file,
+            history.hotspot(),
+            history.commits,
+            history.lines()
+        );
+    }
+    fs::write(path, table).map_err(|e| Error::io(path, e))
+}
+
+/// Lay out the union of all files in the timeline once, so every file keeps its place,
+/// and save it as an HTML page with a play button and a slider to go through the frames.
+/// The files are always sized by their size in bytes.
+pub fn plot_timeline(timeline: &Timeline, path: &Path, options: &PlotOptions) -> Result<()> {
+    let options = PlotOptions {
+        size: SizeMode::Bytes,
+        ..options.clone()
+    };
+    let layout = layout(&timeline.root, &options);
+    let document = render(&layout, &options);
+    let total = (timeline.root.files(), timeline.root.size());
+
+    let paths = layout
+        .files()
+        .iter()
+        .filter_map(|f| match f {
+            Item::File { full_name, .. } => Some(full_name.as_str()),
+            Item::Folder { .. } => None,
+        })
+        .collect::<Vec<_>>();
+    let frames = timeline
+        .frames
+        .iter()
+        .map(|frame| {
+            let radii = paths
+                .iter()
+                .map(|p| {
+                    frame.sizes.get(*p).map_or("0".to_string(), |size| {
+                        format!("{:.2}", get_radius((*size).max(2) as f64, total))
+                    })
+                })
+                .collect::<Vec<_>>();
+            format!(
+                "{{\"label\":{},\"radii\":[{}]}}",
+                json_string(&format!(
+                    "{} {}",
+                    format_date(frame.time),
+                    &frame.commit[..frame.commit.len().min(8)]
+                )),
+                radii.join(",")
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let body = format!(
+        "<div id=\"timeline-controls\">\n<button id=\"timeline-play\" onclick=\"timeline_play()\">Play</button>\n<input id=\"timeline-slider\" type=\"range\" min=\"0\" max=\"{}\" value=\"{}\" oninput=\"timeline_show(this.value)\">\n<span id=\"timeline-label\"></span>\n</div>\n{}\n<script>\nvar timeline_paths = [{}];\nvar timeline_frames = [{}];\n{}</script>",
+        timeline.frames.len().saturating_sub(1),
+        timeline.frames.len().saturating_sub(1),
+        document,
+        paths.iter().map(|p| json_string(p)).collect::<Vec<_>>().join(","),
+        frames.join(",\n"),
+        std::include_str!("timeline.js"),
+    );
+    fs::write(path, html_page(timeline.root.name(), &body)).map_err(|e| Error::io(path, e))
+}
+
+/// Wrap the given body in a minimal HTML page
+fn html_page(title: &str, body: &str) -> String {
+    // Escaped like the text labels in the plot
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body style=\"margin: 0\">\n{}\n</body>\n</html>\n",
+        svg::node::Text::new(title),
+        body
+    )
+}
+
+/// Encode a string as a JSON string, safe to use inside a script tag
+fn json_string(text: &str) -> String {
+    let mut output = String::with_capacity(text.len() + 2);
+    output.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '<' => output.push_str("\\u003c"),
+            c if c.is_control() => output.push_str(&format!("\\u{:04x}", c as u32)),
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+    output
 }
 
 fn make_button(text: &str, id: &str, pos: Point, call_back: &str) -> Group {
@@ -61,37 +301,72 @@ fn make_button(text: &str, id: &str, pos: Point, call_back: &str) -> Group {
                 .set("width", 120)
                 .set("height", 30),
         )
-        .add(
-            Text::new()
-                .set("x", pos.0 + 5.0)
-                .set("y", pos.1 + 20.0)
-                .add(svg::node::Text::new(text)),
-        )
+        .add(Text::new(text).set("x", pos.0 + 5.0).set("y", pos.1 + 20.0))
         .set("class", "btn")
         .set("id", id)
         .set("onclick", call_back)
 }
 
+/// Make a legend with a coloured square and a label for every entry
+fn make_legend(entries: &[(String, String)], pos: Point) -> Group {
+    let mut legend = Group::new().set("class", "legend");
+    for (index, (label, colour)) in entries.iter().enumerate() {
+        let y = pos.1 + index as f64 * 20.0;
+        legend = legend
+            .add(
+                Rectangle::new()
+                    .set("x", pos.0)
+                    .set("y", y)
+                    .set("width", 14)
+                    .set("height", 14)
+                    .set("fill", colour.as_str()),
+            )
+            .add(
+                Text::new(label.as_str())
+                    .set("x", pos.0 + 20.0)
+                    .set("y", y + 12.0),
+            );
+    }
+    legend
+}
+
 fn get_radius(size: f64, total: (i32, f64)) -> f64 {
-    (size.log2() / total.1.log2()) * 1024.0 * 0.5 * 1.0005_f64.powi(total.0)
+    (size.log2() / total.1.log2()) * SIZE * 0.5 * 1.0005_f64.powi(total.0)
 }
 
-fn plot_entities(node: &EntityNode, group: Group, root: &EntityNode) -> (Group, Group) {
+fn plot_entities(
+    node: &EntityNode,
+    group: Group,
+    root: &EntityNode,
+    scheme: &ColourScheme,
+    options: &PlotOptions,
+) -> (Group, Group) {
     match node {
         EntityNode::File(entity, item) => {
             let circle = Circle::new()
                 .set("cx", entity.pos.0)
                 .set("cy", entity.pos.1)
                 .set("r", entity.radius)
-                .set("fill", item.colour());
-            let text = Text::new()
+                .set("fill", item.colour(scheme))
+                .add(Title::new(item.description()));
+            let ring = item.status().map(|status| {
+                Circle::new()
+                    .set("cx", entity.pos.0)
+                    .set("cy", entity.pos.1)
+                    .set("r", entity.radius)
+                    .set("class", format!("status {}", status.name()))
+            });
+            let text = Text::new(item.label())
                 .set("x", entity.pos.0)
-                .set("y", entity.pos.1)
-                .add(svg::node::Text::new(item.name()));
+                .set("y", entity.pos.1);
+            let class = match item.change() {
+                Some(change) => format!("file {}", change.name()),
+                None => "file".to_string(),
+            };
             let mut line_group = Group::new();
             if let Item::File { refs, .. } = item {
                 for reference in refs {
-                    if let Some(Point(x, y)) = find_ref(reference, root) {
+                    if let Some((Point(x, y), _)) = find_node(reference, root) {
                         line_group = line_group.add(
                             Line::new()
                                 .set("x1", entity.pos.0)
@@ -103,65 +378,266 @@ fn plot_entities(node: &EntityNode, group: Group, root: &EntityNode) -> (Group,
                     }
                 }
             }
+            if let Some(coupling) = &options.coupling {
+                line_group = add_coupling_lines(line_group, entity, item, root, coupling);
+            }
+            let mut file_group = Group::new().add(circle);
+            if let Some(ring) = ring {
+                file_group = file_group.add(ring);
+            }
             (
-                group.add(Group::new().add(circle).add(text).set("class", "file")),
+                group.add(
+                    file_group
+                        .add(text)
+                        .set("class", class)
+                        .set("data-path", item.full_name()),
+                ),
                 line_group,
             )
         }
-        EntityNode::Folder(entity, name, items) => {
+        EntityNode::Folder(entity, folder, items) => {
             let circle = Circle::new()
                 .set("cx", entity.pos.0)
                 .set("cy", entity.pos.1)
-                .set("r", entity.radius);
-            let text = Text::new()
+                .set("r", entity.radius)
+                .add(Title::new(folder.description()));
+            let text = Text::new(folder.name())
                 .set("x", entity.pos.0)
-                .set("y", entity.pos.1 - entity.radius)
-                .add(svg::node::Text::new(name));
-            let (transform, text_scale) = get_transform(&entity);
+                .set("y", entity.pos.1 - entity.radius);
+            let (transform, text_scale) = get_transform(entity);
+            let class = match folder.package() {
+                Some(_) => "folder crate",
+                None => "folder",
+            };
             let mut folder_group = Group::new()
                 .add(circle)
                 .add(text)
-                .set("class", "folder")
+                .set("class", class)
                 .set("data-transform", transform)
                 .set("data-text-scale", text_scale);
 
             let mut lines = Group::new();
             for item in items {
-                let res = plot_entities(item, folder_group, root);
+                let res = plot_entities(item, folder_group, root, scheme, options);
                 folder_group = res.0;
                 lines = lines.add(res.1);
             }
+            if let Some(package) = folder.package() {
+                for dependency in &package.dependencies {
+                    if let Some(Point(x, y)) = find_package(dependency, root) {
+                        lines = lines.add(
+                            Line::new()
+                                .set("x1", entity.pos.0)
+                                .set("y1", entity.pos.1)
+                                .set("x2", x)
+                                .set("y2", y)
+                                .set("class", "crate-dependency")
+                                .add(Title::new(format!(
+                                    "{} depends on {}",
+                                    package.name, dependency
+                                ))),
+                        );
+                    }
+                }
+            }
 
             (group.add(folder_group).add(lines), Group::new())
         }
     }
 }
 
-fn find_ref(reference: &str, entity: &EntityNode) -> Option<Point> {
-    match entity {
-        EntityNode::File(place, Item::File { name, .. }) => {
-            if name == reference {
-                return Some(place.pos);
-            } else {
-                None
+/// Add a line to every file that is changed together with the given file often enough,
+/// each pair is only drawn once. Stronger coupling gives a wider line.
+fn add_coupling_lines(
+    mut lines: Group,
+    entity: &Entity,
+    item: &Item,
+    root: &EntityNode,
+    options: &CouplingOptions,
+) -> Group {
+    let (full_name, history) = match item {
+        Item::File {
+            full_name,
+            history: Some(history),
+            ..
+        } => (full_name, history),
+        _ => return lines,
+    };
+    let mut coupled = history.coupled.iter().collect::<Vec<_>>();
+    coupled.sort();
+    for (other, shared) in coupled {
+        if other <= full_name || *shared < options.min_commits {
+            continue;
+        }
+        if let Some((
+            pos,
+            Item::File {
+                history: Some(other_history),
+                ..
+            },
+        )) = find_node(other, root)
+        {
+            let strength = *shared as f64 * 2.0 / (history.commits + other_history.commits) as f64;
+            if strength >= options.min_strength {
+                lines = lines.add(
+                    Line::new()
+                        .set("x1", entity.pos.0)
+                        .set("y1", entity.pos.1)
+                        .set("x2", pos.0)
+                        .set("y2", pos.1)
+                        .set("class", "cochange")
+                        .set("style", format!("--coupling: {:.2}", strength))
+                        .add(Title::new(format!(
+                            "{} and {}\nchanged together in {} commits",
+                            full_name, other, shared
+                        ))),
+                );
             }
         }
-        EntityNode::Folder(_, _, items) => {
-            for item in items {
-                if let Some(p) = find_ref(reference, item) {
-                    return Some(p);
-                }
+    }
+    lines
+}
+
+/// Find the file or folder with the given path relative to the scanned folder
+fn find_node<'a>(path: &str, root: &EntityNode<'a>) -> Option<(Point, &'a Item)> {
+    if let EntityNode::File(place, item) = root {
+        // A single scanned file
+        return (item.full_name() == path).then_some((place.pos, *item));
+    }
+    let mut node = root;
+    for name in path.split('/').filter(|n| !n.is_empty()) {
+        node = match node {
+            EntityNode::Folder(_, _, items) => items.iter().find(|i| match i {
+                EntityNode::File(_, item) | EntityNode::Folder(_, item, _) => item.name() == name,
+            })?,
+            EntityNode::File(..) => return None,
+        };
+    }
+    match node {
+        EntityNode::File(place, item) | EntityNode::Folder(place, item, _) => {
+            Some((place.pos, *item))
+        }
+    }
+}
+
+/// Every external package used by any folder, together with the position of that folder
+fn layout_dependencies<'a>(entity: &EntityNode<'a>) -> Vec<(Point, &'a Dependency)> {
+    match entity {
+        EntityNode::File(..) => Vec::new(),
+        EntityNode::Folder(place, folder, items) => folder
+            .dependencies()
+            .iter()
+            .map(|d| (place.pos, d))
+            .chain(items.iter().flat_map(layout_dependencies))
+            .collect(),
+    }
+}
+
+/// Place the external packages in a ring around the root and draw a line from every folder
+/// that uses them, `None` if there are no external packages
+fn plot_dependencies(layout: &EntityNode) -> Option<Group> {
+    let uses = layout_dependencies(layout);
+    let mut packages = uses.iter().map(|(_, d)| *d).collect::<Vec<_>>();
+    packages.sort_by(|a, b| {
+        (a.ecosystem, &a.name, &a.version).cmp(&(b.ecosystem, &b.name, &b.version))
+    });
+    packages
+        .dedup_by(|a, b| (a.ecosystem, &a.name, &a.version) == (b.ecosystem, &b.name, &b.version));
+    if packages.is_empty() {
+        return None;
+    }
+
+    let root = layout.entity();
+    let distance = root.radius + SATELLITE_DISTANCE;
+    let position = |package: &Dependency| {
+        let index = packages.iter().position(|p| *p == package).unwrap_or(0);
+        let angle = index as f64 / packages.len() as f64 * std::f64::consts::TAU
+            - std::f64::consts::FRAC_PI_2;
+        Point(
+            root.pos.0 + distance * angle.cos(),
+            root.pos.1 + distance * angle.sin(),
+        )
+    };
+    let mut lines = Group::new();
+    for (folder, package) in &uses {
+        let Point(x, y) = position(package);
+        lines = lines.add(
+            Line::new()
+                .set("x1", folder.0)
+                .set("y1", folder.1)
+                .set("x2", x)
+                .set("y2", y)
+                .set(
+                    "class",
+                    format!("dependency-use {}", package.ecosystem.name()),
+                ),
+        );
+    }
+    let mut satellites = Group::new();
+    for package in &packages {
+        let Point(x, y) = position(package);
+        satellites = satellites.add(
+            Group::new()
+                .set("class", format!("dependency {}", package.ecosystem.name()))
+                .add(
+                    Circle::new()
+                        .set("cx", x)
+                        .set("cy", y)
+                        .set("r", SATELLITE_RADIUS)
+                        .add(Title::new(format!(
+                            "{} {} ({})\nused by {} folders",
+                            package.name,
+                            package.version,
+                            package.ecosystem.name(),
+                            package.users.len()
+                        ))),
+                )
+                .add(
+                    Text::new(package.name.as_str())
+                        .set("x", x)
+                        .set("y", y + SATELLITE_RADIUS * 2.0),
+                ),
+        );
+    }
+    Some(
+        Group::new()
+            .set("class", "dependencies")
+            .add(lines)
+            .add(satellites),
+    )
+}
+
+/// Check if any folder is marked as a crate
+fn has_packages(entity: &EntityNode) -> bool {
+    match entity {
+        EntityNode::File(..) => false,
+        EntityNode::Folder(_, folder, items) => {
+            folder.package().is_some() || items.iter().any(has_packages)
+        }
+    }
+}
+
+/// Find the folder of the crate with the given name
+fn find_package(name: &str, entity: &EntityNode) -> Option<Point> {
+    match entity {
+        EntityNode::File(..) => None,
+        EntityNode::Folder(place, folder, items) => {
+            if folder.package().is_some_and(|p| p.name == name) {
+                Some(place.pos)
+            } else {
+                items.iter().find_map(|i| find_package(name, i))
             }
-            None
         }
-        _ => panic!("Not possible"),
     }
 }
 
+/// The place of a single file or folder in the layout
 #[derive(Debug, Clone, Copy)]
-struct Entity {
-    pos: Point,
-    radius: f64,
+pub struct Entity {
+    /// The center of the circle
+    pub pos: Point,
+    /// The radius of the circle
+    pub radius: f64,
     speed: Point,
 }
 
@@ -183,10 +659,11 @@ impl Entity {
     }
 }
 
+/// A laid out structure, mirroring the [`Item`] tree it was made from
 #[derive(Debug, Clone)]
-enum EntityNode<'a> {
+pub enum EntityNode<'a> {
     File(Entity, &'a Item),
-    Folder(Entity, String, Vec<EntityNode<'a>>),
+    Folder(Entity, &'a Item, Vec<EntityNode<'a>>),
 }
 
 impl<'a> EntityNode<'a> {
@@ -202,6 +679,13 @@ impl<'a> EntityNode<'a> {
             EntityNode::Folder(e, _, _) => e,
         }
     }
+    /// All files in this node
+    pub fn files(&self) -> Vec<&'a Item> {
+        match self {
+            EntityNode::File(_, item) => vec![*item],
+            EntityNode::Folder(_, _, items) => items.iter().flat_map(|i| i.files()).collect(),
+        }
+    }
     pub fn set_entity(self, entity: Entity) -> Self {
         match self {
             EntityNode::File(_, i) => EntityNode::File(entity, i),
@@ -210,29 +694,29 @@ impl<'a> EntityNode<'a> {
     }
 }
 
-fn plot_item(item: &Item, area: Area, total: (i32, f64)) -> EntityNode {
+fn plot_item(item: &Item, area: Area, total: (i32, f64), mode: SizeMode) -> EntityNode<'_> {
     match item {
         Item::File { .. } => EntityNode::File(
             Entity {
                 pos: area.center(),
-                radius: get_radius(item.size(), total),
+                radius: get_radius(item.size_by(mode), total),
                 speed: Point(0.0, 0.0),
             },
             item,
         ),
-        Item::Folder { name, items } => {
+        Item::Folder { items, .. } => {
             let base = (items.len() as f64).sqrt().ceil() as usize;
             EntityNode::Folder(
                 Entity {
                     pos: area.center(),
-                    radius: get_radius(item.size(), total),
+                    radius: get_radius(item.size_by(mode), total),
                     speed: Point(0.0, 0.0),
                 },
-                name.to_string(),
+                item,
                 items
                     .iter()
                     .zip(area.split_evenly((base, base)))
-                    .map(|(i, a)| plot_item(i, a, total))
+                    .map(|(i, a)| plot_item(i, a, total, mode))
                     .collect(),
             )
         }
@@ -240,14 +724,11 @@ fn plot_item(item: &Item, area: Area, total: (i32, f64)) -> EntityNode {
 }
 
 fn improve_positions(entity: &mut EntityNode) {
-    match entity {
-        EntityNode::Folder(folder_entity, _, items) => {
-            improve_folder_positions(folder_entity, items);
-            for item in items {
-                improve_positions(item)
-            }
+    if let EntityNode::Folder(folder_entity, _, items) = entity {
+        improve_folder_positions(folder_entity, items);
+        for item in items {
+            improve_positions(item)
         }
-        _ => (),
     }
 }
 
@@ -259,7 +740,7 @@ fn improve_folder_positions(entity: &mut Entity, items: &mut Vec<EntityNode>) {
         let mut vec = (0..items.len()).collect::<Vec<_>>();
         vec.shuffle(&mut thread_rng());
         for index in vec {
-            let mut item = items[index].entity().clone();
+            let mut item = *items[index].entity();
             // update speed
             item.speed = (center - item.pos).normalize() * 0.5 + item.speed;
             //println!(
@@ -331,8 +812,8 @@ fn improve_folder_positions(entity: &mut Entity, items: &mut Vec<EntityNode>) {
 fn shrink_folder_sizes(entity: EntityNode) -> EntityNode {
     match entity {
         EntityNode::Folder(mut folder_entity, name, mut items) => {
-            for n in 0..items.len() {
-                items[n] = shrink_folder_sizes(items[n].clone())
+            for item in items.iter_mut() {
+                *item = shrink_folder_sizes(item.clone())
             }
             if !items.is_empty() {
                 folder_entity.radius = 0.0;
@@ -355,7 +836,7 @@ fn shrink_folder_sizes(entity: EntityNode) -> EntityNode {
 fn get_transform(entity: &Entity) -> (String, String) {
     let area = entity.bounding_box();
     let size = (area.end_x - area.start_x).min(area.end_y - area.start_y) * 1.5;
-    let scale = 1024.0 / size;
+    let scale = SIZE / size;
     let transform_x = area.start_x + (area.end_x - area.start_x) / 2.0 - size / 2.0;
     let transform_y = area.start_y + (area.end_y - area.start_y) / 2.0 - size / 2.0;
     (
@@ -366,3 +847,15 @@ fn get_transform(entity: &Entity) -> (String, String) {
         (1.0 / scale).to_string(),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn html_page_escapes_the_title() {
+        let page = html_page("a<b>&c", "<svg></svg>");
+        assert!(page.contains("<title>a&lt;b&gt;&amp;c</title>"));
+        assert!(page.contains("\n<svg></svg>\n"));
+    }
+}
@@ -0,0 +1,292 @@
+use crate::error::SyntaxError;
+use crate::refs::{Context, Extractor, Reference, ReferenceKind};
+use crate::structure::{join_path, parent_path};
+
+/// Finds the imports in Python source files: `import a.b`, `from .a import b` and
+/// `from pkg import mod`. Imports are resolved to modules (`a/b.py`) and packages
+/// (`a/b/__init__.py`), also below namespace packages without `__init__.py`. Absolute
+/// imports are looked for relative to every folder above the file and their `src` folder,
+/// the nearest first, so src-layouts and nested projects work without configuration.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PythonExtractor;
+
+impl Extractor for PythonExtractor {
+    fn language(&self) -> &str {
+        "python"
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        path.ends_with(".py") || path.ends_with(".pyi")
+    }
+
+    fn extract(&self, path: &str, contents: &str) -> Result<Vec<Reference>, SyntaxError> {
+        let folder = parent_path(path);
+        let mut refs = Vec::new();
+        for statement in statements(contents) {
+            let words = statement.split_whitespace().collect::<Vec<_>>();
+            match words.first() {
+                Some(&"import") => {
+                    // `import a.b as c, d`
+                    for name in words[1..].join(" ").split(',') {
+                        if let Some(module) = name.split_whitespace().next() {
+                            refs.push(Reference::new(ReferenceKind::Import, module, folder));
+                        }
+                    }
+                }
+                Some(&"from") if words.get(2) == Some(&"import") => {
+                    let module = words[1];
+                    // Every imported name can be a module itself, otherwise it resolves to
+                    // the module it is imported from
+                    let names = words[3..].join(" ");
+                    for name in names.split(',') {
+                        let name = match name.split_whitespace().next() {
+                            Some(name) => name,
+                            None => continue,
+                        };
+                        let target = if name == "*" {
+                            module.to_string()
+                        } else if module.ends_with('.') {
+                            format!("{}{}", module, name)
+                        } else {
+                            format!("{}.{}", module, name)
+                        };
+                        refs.push(Reference::new(ReferenceKind::Import, target, folder));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(refs)
+    }
+
+    fn resolve(&self, path: &str, reference: &Reference, context: &Context) -> Option<String> {
+        let exists = &|p: &str| context.exists(p);
+        let target = reference.target.as_str();
+        let relative = target.trim_start_matches('.');
+        let segments = relative
+            .split('.')
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>();
+        let dots = target.len() - relative.len();
+        if dots > 0 {
+            // One dot is the package of the file, every next dot the package above it
+            let mut base = parent_path(path);
+            for _ in 1..dots {
+                if base.is_empty() {
+                    return None;
+                }
+                base = parent_path(base);
+            }
+            find_module(base, &segments, exists)
+                .or_else(|| Some(join_path(base, "__init__.py")).filter(|p| exists(p)))
+        } else {
+            let mut base = Some(parent_path(path));
+            while let Some(folder) = base {
+                for root in [folder.to_string(), join_path(folder, "src")] {
+                    if let Some(found) = find_module(&root, &segments, exists) {
+                        return Some(found);
+                    }
+                }
+                base = (!folder.is_empty()).then(|| parent_path(folder));
+            }
+            None
+        }
+    }
+}
+
+/// Find the file of the longest prefix of the segments that is a module or package in the
+/// given folder
+fn find_module(folder: &str, segments: &[&str], exists: &dyn Fn(&str) -> bool) -> Option<String> {
+    (1..=segments.len()).rev().find_map(|length| {
+        let path = join_path(folder, &segments[..length].join("/"));
+        [
+            format!("{}.py", path),
+            format!("{}.pyi", path),
+            join_path(&path, "__init__.py"),
+        ]
+        .into_iter()
+        .find(|c| exists(c))
+    })
+}
+
+/// Split Python source into its statements, leaving out comments and the contents of
+/// strings, and joining lines continued with a backslash or inside brackets
+fn statements(contents: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+    let mut chars = contents.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '#' => while chars.next_if(|c| *c != '\n').is_some() {},
+            '"' | '\'' => {
+                let triple = chars.next_if_eq(&c).is_some();
+                if triple && chars.next_if_eq(&c).is_none() {
+                    // An empty string
+                    current.push_str("\"\"");
+                    continue;
+                }
+                let mut quotes = 0;
+                while let Some(s) = chars.next() {
+                    match s {
+                        '\\' => {
+                            chars.next();
+                            quotes = 0;
+                        }
+                        s if s == c => {
+                            quotes += 1;
+                            if !triple || quotes == 3 {
+                                break;
+                            }
+                        }
+                        '\n' if !triple => break,
+                        _ => quotes = 0,
+                    }
+                }
+                current.push_str("\"\"");
+            }
+            '\\' if chars.next_if_eq(&'\n').is_some() => current.push(' '),
+            '(' | '[' | '{' => {
+                depth += 1;
+                current.push(' ');
+            }
+            ')' | ']' | '}' => {
+                depth = depth.saturating_sub(1);
+                current.push(' ');
+            }
+            '\n' if depth > 0 => current.push(' '),
+            '\n' | ';' => statements.push(std::mem::take(&mut current)),
+            c => current.push(c),
+        }
+    }
+    statements.push(current);
+    statements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn targets(path: &str, contents: &str) -> Vec<String> {
+        PythonExtractor
+            .extract(path, contents)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.target)
+            .collect()
+    }
+
+    fn resolve(path: &str, target: &str, files: &[&str]) -> Option<String> {
+        let reference = Reference::new(ReferenceKind::Import, target, parent_path(path));
+        let exists = |p: &str| files.contains(&p);
+        PythonExtractor.resolve(path, &reference, &Context::new(&exists, &|_| None))
+    }
+
+    fn trimmed(contents: &str) -> Vec<String> {
+        statements(contents)
+            .iter()
+            .map(|s| s.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|s| !s.is_empty())
+            .collect()
+    }
+
+    #[test]
+    fn statements_leave_out_strings_and_comments() {
+        let source = concat!(
+            "x = \"import os\"  # import sys\n",
+            "y = 'it\\'s; import re'\n",
+            "\"\"\"\n",
+            "import json\n",
+            "\"\"\"\n",
+            "z = ''; import a\n",
+        );
+        assert_eq!(
+            trimmed(source),
+            vec!["x = \"\"", "y = \"\"", "\"\"", "z = \"\"", "import a"]
+        );
+        assert_eq!(targets("m.py", source), vec!["a"]);
+    }
+
+    #[test]
+    fn statements_join_continuation_lines() {
+        let source = concat!(
+            "import a, \\\n",
+            "    b\n",
+            "x = call(\n",
+            "    1,\n",
+            "    2)\n",
+        );
+        assert_eq!(trimmed(source), vec!["import a, b", "x = call 1, 2"]);
+        assert_eq!(targets("m.py", source), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn imports() {
+        let source = concat!(
+            "import os.path as p, sys\n",
+            "from pkg import mod as m, other\n",
+            "from pkg.sub import *\n",
+        );
+        assert_eq!(
+            targets("m.py", source),
+            vec!["os.path", "sys", "pkg.mod", "pkg.other", "pkg.sub"]
+        );
+    }
+
+    #[test]
+    fn parenthesised_imports() {
+        let source = concat!(
+            "from pkg import (\n",
+            "    a,  # the first\n",
+            "    b as c,\n",
+            ")\n",
+        );
+        assert_eq!(targets("m.py", source), vec!["pkg.a", "pkg.b"]);
+    }
+
+    #[test]
+    fn relative_imports() {
+        let source = "from . import a\nfrom ..pkg import y\nfrom .. import z\n";
+        assert_eq!(
+            targets("app/sub/m.py", source),
+            vec![".a", "..pkg.y", "..z"]
+        );
+
+        let files = [
+            "app/__init__.py",
+            "app/pkg/__init__.py",
+            "app/pkg/y.py",
+            "app/sub/a.py",
+            "app/sub/m.py",
+        ];
+        assert_eq!(
+            resolve("app/sub/m.py", ".a", &files),
+            Some("app/sub/a.py".to_string())
+        );
+        assert_eq!(
+            resolve("app/sub/m.py", "..pkg.y", &files),
+            Some("app/pkg/y.py".to_string())
+        );
+        // A name that is not a module resolves to the package it is imported from
+        assert_eq!(
+            resolve("app/sub/m.py", "..pkg.name", &files),
+            Some("app/pkg/__init__.py".to_string())
+        );
+        assert_eq!(
+            resolve("app/sub/m.py", "..z", &files),
+            Some("app/__init__.py".to_string())
+        );
+        // Above the scanned folder
+        assert_eq!(resolve("m.py", "...pkg", &files), None);
+    }
+
+    #[test]
+    fn absolute_imports_from_src_layouts() {
+        let files = ["src/pkg/__init__.py", "src/pkg/mod.py", "tests/test_mod.py"];
+        assert_eq!(
+            resolve("tests/test_mod.py", "pkg.mod", &files),
+            Some("src/pkg/mod.py".to_string())
+        );
+        assert_eq!(resolve("tests/test_mod.py", "os.path", &files), None);
+    }
+}
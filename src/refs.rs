@@ -0,0 +1,333 @@
+use crate::c::CExtractor;
+use crate::error::*;
+use crate::go::GoExtractor;
+use crate::java::JavaExtractor;
+use crate::javascript::JavaScriptExtractor;
+use crate::python::PythonExtractor;
+use crate::rust::RustExtractor;
+use crate::structure::parent_path;
+use crate::web::WebExtractor;
+
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// How a reference is written in a source file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferenceKind {
+    /// An import of a module or package by name, like `use crate::a::b` or `import a.b`
+    Import,
+    /// A declaration of a child module that lives in its own file, like `mod a;`
+    Module,
+    /// A path to a file, like `include_str!("a.txt")`
+    Include,
+}
+
+/// A reference found in a source file, before it is resolved to a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    pub kind: ReferenceKind,
+    /// The target as written in the source, in the syntax of the language
+    pub target: String,
+    /// The folder (relative to the scanned folder) the target is relative to, the meaning
+    /// depends on the language and kind of reference
+    pub scope: String,
+}
+
+impl Reference {
+    pub fn new(kind: ReferenceKind, target: impl Into<String>, scope: impl Into<String>) -> Self {
+        Reference {
+            kind,
+            target: target.into(),
+            scope: scope.into(),
+        }
+    }
+}
+
+/// The scanned folder (or revision) as an [`Extractor`] sees it while resolving references:
+/// which files and folders there are and what is in them, like in configuration files. Paths
+/// are relative to the scanned folder, a path ending with `/` is a folder. There is one
+/// context for every scan.
+pub struct Context<'a> {
+    exists: &'a dyn Fn(&str) -> bool,
+    read: &'a dyn Fn(&str) -> Option<String>,
+    /// The values parsed from files so far, by their type and the path of the file
+    parsed: RefCell<HashMap<(TypeId, String), Parsed>>,
+}
+
+/// A value parsed from a file, `None` if the file could not be read or parsed
+type Parsed = Option<Rc<dyn Any>>;
+
+impl<'a> Context<'a> {
+    /// A context with the given functions to check which paths exist and to read a file,
+    /// `read` gives `None` if there is no such file or it is not valid UTF-8
+    pub fn new(exists: &'a dyn Fn(&str) -> bool, read: &'a dyn Fn(&str) -> Option<String>) -> Self {
+        Context {
+            exists,
+            read,
+            parsed: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Check if there is a file at the given path, or a folder for a path ending with `/`
+    pub fn exists(&self, path: &str) -> bool {
+        (self.exists)(path)
+    }
+
+    /// Read the file at the given path, `None` if there is no such file or it is not valid
+    /// UTF-8
+    pub fn read(&self, path: &str) -> Option<String> {
+        (self.read)(path)
+    }
+
+    /// Read the file at the given path and parse it into a value of type `T`, only once per
+    /// scan: later calls for the same path and type get the value parsed the first time
+    pub fn parse<T: Any>(
+        &self,
+        path: &str,
+        parse: impl FnOnce(&str) -> Option<T>,
+    ) -> Option<Rc<T>> {
+        let key = (TypeId::of::<T>(), path.to_string());
+        if let Some(value) = self.parsed.borrow().get(&key) {
+            return value.clone().and_then(|v| v.downcast().ok());
+        }
+        // Parsing can parse other files, like the configuration a configuration extends
+        let value = self.read(path).and_then(|text| parse(&text)).map(Rc::new);
+        self.parsed
+            .borrow_mut()
+            .insert(key, value.clone().map(|v| v as Rc<dyn Any>));
+        value
+    }
+}
+
+/// Finds the references to other files in the source files of one language. Paths are
+/// relative to the scanned folder and use `/` as separator.
+pub trait Extractor: Send + Sync {
+    /// A short name of the language, like `rust`
+    fn language(&self) -> &str;
+
+    /// Check if this extractor handles the file at the given path
+    fn matches(&self, path: &str) -> bool;
+
+    /// Find the references in the contents of the file at the given path, or describe why
+    /// the contents could not be parsed
+    fn extract(
+        &self,
+        path: &str,
+        contents: &str,
+    ) -> std::result::Result<Vec<Reference>, SyntaxError>;
+
+    /// Get the path of the file a reference in the file at the given path refers to, or of the
+    /// folder followed by `/` for languages that refer to packages (`/` alone for the scanned
+    /// folder). The context tells which files and folders there are and reads configuration
+    /// files, like `tsconfig.json`. `None` if it refers to nothing in the folder.
+    fn resolve(&self, path: &str, reference: &Reference, context: &Context) -> Option<String>;
+
+    /// Whether [`Extractor::resolve`] is likely to read the file at the given path with
+    /// [`Context::read`]. Scans of a revision read these files up front together with the
+    /// source files, other files are read one by one when asked for.
+    fn reads(&self, path: &str) -> bool {
+        let _ = path;
+        false
+    }
+}
+
+/// The extractors used while scanning, picked by the file they match. The last registered
+/// extractor that matches a file is used, and registering an extractor for a language replaces
+/// the one registered for it before, like a built in one.
+#[derive(Clone)]
+pub struct Extractors {
+    extractors: Vec<Arc<dyn Extractor>>,
+}
+
+impl Extractors {
+    /// A registry without any extractors, no references are found with it
+    pub fn empty() -> Self {
+        Extractors {
+            extractors: Vec::new(),
+        }
+    }
+
+    /// Add an extractor, it takes precedence over the ones added before and replaces the one
+    /// for the same language
+    pub fn register(&mut self, extractor: impl Extractor + 'static) {
+        self.extractors
+            .retain(|e| e.language() != extractor.language());
+        self.extractors.push(Arc::new(extractor))
+    }
+
+    /// Get the extractor for the file at the given path, if any
+    pub fn find(&self, path: &str) -> Option<&dyn Extractor> {
+        self.extractors
+            .iter()
+            .rev()
+            .find(|e| e.matches(path))
+            .map(|e| e.as_ref())
+    }
+
+    /// Whether any extractor is likely to read the file at the given path while resolving,
+    /// see [`Extractor::reads`]
+    pub fn reads(&self, path: &str) -> bool {
+        self.extractors.iter().any(|e| e.reads(path))
+    }
+
+    /// The languages of all extractors, in the order they were registered
+    pub fn languages(&self) -> Vec<&str> {
+        self.extractors.iter().map(|e| e.language()).collect()
+    }
+}
+
+/// The built in extractors
+impl Default for Extractors {
+    fn default() -> Self {
+        let mut extractors = Extractors::empty();
+        extractors.register(RustExtractor);
+        extractors.register(PythonExtractor);
+        extractors.register(JavaScriptExtractor);
+        extractors.register(CExtractor::default());
+        extractors.register(GoExtractor);
+        extractors.register(JavaExtractor);
+        extractors.register(WebExtractor);
+        extractors
+    }
+}
+
+impl fmt::Debug for Extractors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.languages()).finish()
+    }
+}
+
+/// Find and resolve the references in a file, see [`Extractor`]. References to the file
+/// itself, to the folder it is in or to paths that do not exist are left out. Folders are
+/// given without the trailing `/`, so the scanned folder itself is the empty path.
+pub(crate) fn find_refs(
+    extractor: &dyn Extractor,
+    path: &Path,
+    full_name: &str,
+    contents: &[u8],
+    context: &Context,
+    warnings: &mut Vec<Warning>,
+) -> Vec<String> {
+    let contents = match std::str::from_utf8(contents) {
+        Ok(s) => s,
+        Err(_) => {
+            warnings.push(Warning::new(path, WarningKind::NotUtf8));
+            return vec![];
+        }
+    };
+    let references = match extractor.extract(full_name, contents) {
+        Ok(references) => references,
+        Err(e) => {
+            warnings.push(Warning::new(path, WarningKind::InvalidSyntax(e)));
+            return vec![];
+        }
+    };
+    let mut refs = Vec::new();
+    for reference in references {
+        let target = match extractor.resolve(full_name, &reference, context) {
+            Some(target) if target != full_name && context.exists(&target) => target,
+            _ => continue,
+        };
+        let target = match target.strip_suffix('/') {
+            Some(folder) if folder == parent_path(full_name) => continue,
+            Some(folder) => folder.to_string(),
+            None => target,
+        };
+        if !refs.contains(&target) {
+            refs.push(target);
+        }
+    }
+    refs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn refs(full_name: &str, files: &[&str]) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let exists = |p: &str| files.contains(&p);
+        let read = |p: &str| (p == "go.mod").then(|| "module m\n".to_string());
+        let contents = "package main\n\nimport (\n\t\"m\"\n\t\"m/a\"\n\t\"m/cmd\"\n\t\"fmt\"\n)\n";
+        let refs = find_refs(
+            &GoExtractor,
+            Path::new(full_name),
+            full_name,
+            contents.as_bytes(),
+            &Context::new(&exists, &read),
+            &mut warnings,
+        );
+        assert!(warnings.is_empty());
+        refs
+    }
+
+    #[test]
+    fn parsed_once() {
+        let exists = |_: &str| true;
+        let reads = RefCell::new(0);
+        let read = |p: &str| {
+            *reads.borrow_mut() += 1;
+            Some(p.to_string())
+        };
+        let context = Context::new(&exists, &read);
+        let parsed = context.parse::<usize>("a", |text| Some(text.len()));
+        assert_eq!(parsed.as_deref(), Some(&1));
+        assert_eq!(
+            context.parse::<usize>("a", |_| Some(2)).as_deref(),
+            Some(&1)
+        );
+        // Every type is parsed once
+        assert_eq!(
+            context
+                .parse("a", |text| Some(text.to_string()))
+                .as_deref()
+                .map(String::as_str),
+            Some("a")
+        );
+        assert_eq!(context.parse::<u8>("b", |_| None), None);
+        assert_eq!(context.parse::<u8>("b", |_| Some(1)), None);
+        assert_eq!(*reads.borrow(), 3);
+    }
+
+    #[test]
+    fn register_replaces_the_same_language() {
+        let mut extractors = Extractors::default();
+        assert_eq!(
+            extractors.languages().iter().filter(|l| **l == "c").count(),
+            1
+        );
+        extractors.register(CExtractor {
+            include_dirs: vec!["include".to_string()],
+            compile_commands: None,
+        });
+        let languages = extractors.languages();
+        assert_eq!(languages.iter().filter(|l| **l == "c").count(), 1);
+        assert_eq!(languages.last(), Some(&"c"));
+        assert!(extractors.find("a.h").is_some());
+    }
+
+    #[test]
+    fn configuration_files_read() {
+        let extractors = Extractors::default();
+        assert!(extractors.reads("web/tsconfig.json"));
+        assert!(extractors.reads("tsconfig.base.json"));
+        assert!(extractors.reads("svc/go.mod"));
+        assert!(extractors.reads("compile_commands.json"));
+        assert!(!extractors.reads("build/compile_commands.json"));
+        assert!(!extractors.reads("package.json"));
+        assert!(!Extractors::empty().reads("go.mod"));
+    }
+
+    #[test]
+    fn folder_references() {
+        let files = ["/", "go.mod", "main.go", "a/", "cmd/", "cmd/main.go"];
+        // The scanned folder is the empty path, the folder of the file itself is left out
+        assert_eq!(refs("cmd/main.go", &files), vec!["", "a"]);
+        assert_eq!(refs("main.go", &files), vec!["a", "cmd"]);
+    }
+}
@@ -0,0 +1,554 @@
+use crate::error::SyntaxError;
+use crate::refs::{Context, Extractor, Reference, ReferenceKind};
+use crate::structure::{join_path, parent_path};
+
+use syn::punctuated::Punctuated;
+use syn::visit::{self, Visit};
+use syn::{Expr, ItemMod, ItemUse, Lit, LitStr, Macro, Meta, Token, UseTree};
+
+/// Finds the references in Rust source files: `use` (including grouped and nested
+/// imports), `mod` (with `#[path]`, also inside inline modules) and `include!`,
+/// `include_str!` and `include_bytes!`. Imports and modules are resolved following the
+/// module tree of the crate, handling `mod.rs` and `super`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RustExtractor;
+
+impl Extractor for RustExtractor {
+    fn language(&self) -> &str {
+        "rust"
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        path.ends_with(".rs")
+    }
+
+    fn extract(&self, path: &str, contents: &str) -> Result<Vec<Reference>, SyntaxError> {
+        let file = syn::parse_file(contents).map_err(|e| SyntaxError::new(e.to_string()))?;
+        let module = Module::new(path, &|_| false);
+        let mut visitor = RefVisitor {
+            own: module.own.clone(),
+            module,
+            refs: Vec::new(),
+        };
+        visitor.visit_file(&file);
+        Ok(visitor.refs)
+    }
+
+    fn resolve(&self, path: &str, reference: &Reference, context: &Context) -> Option<String> {
+        let exists = &|p: &str| context.exists(p);
+        match reference.kind {
+            ReferenceKind::Import => {
+                let segments = reference.target.split("::").collect::<Vec<_>>();
+                Module::new(path, exists).resolve_use(&reference.scope, &segments, exists)
+            }
+            ReferenceKind::Module => Module::new(path, exists).resolve_in(
+                &reference.scope,
+                &[reference.target.as_str()],
+                exists,
+            ),
+            ReferenceKind::Include => Some(join_path(&reference.scope, &reference.target)),
+        }
+    }
+}
+
+/// Walks the syntax tree of a file and collects the references. Imports and modules are
+/// scoped to the folder of the (inline) module they are in, includes to their folder.
+struct RefVisitor {
+    module: Module,
+    /// The folder of the (inline) module that is being walked
+    own: String,
+    refs: Vec<Reference>,
+}
+
+impl RefVisitor {
+    /// The folder paths in `#[path]` are relative to: the folder of the file, or the folder
+    /// of the inline module they are in
+    fn relative_folder(&self) -> &str {
+        if self.own == self.module.own {
+            &self.module.folder
+        } else {
+            &self.own
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for RefVisitor {
+    fn visit_item_use(&mut self, item: &'ast ItemUse) {
+        let mut paths = Vec::new();
+        use_paths(&item.tree, &mut Vec::new(), &mut paths);
+        for path in paths {
+            self.refs.push(Reference::new(
+                ReferenceKind::Import,
+                path.join("::"),
+                self.own.as_str(),
+            ));
+        }
+    }
+
+    fn visit_item_mod(&mut self, item: &'ast ItemMod) {
+        let name = item.ident.to_string();
+        let path = item.attrs.iter().find_map(|attr| match &attr.meta {
+            Meta::NameValue(meta) if meta.path.is_ident("path") => match &meta.value {
+                Expr::Lit(expr) => match &expr.lit {
+                    Lit::Str(path) => Some(path.value()),
+                    _ => None,
+                },
+                _ => None,
+            },
+            _ => None,
+        });
+        if item.content.is_some() {
+            let own = std::mem::take(&mut self.own);
+            self.own = join_path(&own, path.as_deref().unwrap_or(&name));
+            visit::visit_item_mod(self, item);
+            self.own = own;
+        } else {
+            let reference = match path {
+                Some(path) => Reference::new(ReferenceKind::Include, path, self.relative_folder()),
+                None => Reference::new(ReferenceKind::Module, name, self.own.as_str()),
+            };
+            self.refs.push(reference);
+        }
+    }
+
+    fn visit_macro(&mut self, mac: &'ast Macro) {
+        let name = mac.path.segments.last().map(|s| s.ident.to_string());
+        if matches!(
+            name.as_deref(),
+            Some("include" | "include_str" | "include_bytes")
+        ) {
+            if let Ok(path) = mac.parse_body::<LitStr>() {
+                // Unlike `#[path]` these are always relative to the file itself
+                self.refs.push(Reference::new(
+                    ReferenceKind::Include,
+                    path.value(),
+                    self.module.folder.as_str(),
+                ));
+            }
+        } else if let Ok(args) =
+            mac.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated)
+        {
+            // The arguments of macros like `format!` can contain includes as well
+            for arg in &args {
+                self.visit_expr(arg);
+            }
+        }
+        visit::visit_macro(self, mac);
+    }
+}
+
+/// Collect the paths of all names imported by a use tree, a glob or `self` gives the path
+/// of the module itself
+fn use_paths(tree: &UseTree, prefix: &mut Vec<String>, paths: &mut Vec<Vec<String>>) {
+    match tree {
+        UseTree::Path(path) => {
+            prefix.push(path.ident.to_string());
+            use_paths(&path.tree, prefix, paths);
+            prefix.pop();
+        }
+        UseTree::Name(name) if name.ident == "self" => paths.push(prefix.clone()),
+        UseTree::Name(name) => {
+            let mut path = prefix.clone();
+            path.push(name.ident.to_string());
+            paths.push(path);
+        }
+        UseTree::Rename(rename) => {
+            let mut path = prefix.clone();
+            path.push(rename.ident.to_string());
+            paths.push(path);
+        }
+        UseTree::Glob(_) => paths.push(prefix.clone()),
+        UseTree::Group(group) => {
+            for tree in &group.items {
+                use_paths(tree, prefix, paths);
+            }
+        }
+    }
+}
+
+/// Where a Rust source file sits in the module tree of its crate
+struct Module {
+    /// The path of the file
+    file: String,
+    /// The folder containing the file
+    folder: String,
+    /// The folder containing the files of the child modules: the folder of the file for
+    /// crate roots and `mod.rs`, otherwise a folder with the name of the file
+    own: String,
+    /// The folder of the crate root, if found
+    root: Option<String>,
+}
+
+impl Module {
+    fn new(full_name: &str, exists: &dyn Fn(&str) -> bool) -> Self {
+        let folder = parent_path(full_name).to_string();
+        let name = full_name.rsplit('/').next().unwrap_or_default();
+        let stem = name.strip_suffix(".rs").unwrap_or(name);
+        let folder_name = folder.rsplit('/').next().unwrap_or_default();
+        let is_root = matches!(name, "lib.rs" | "main.rs" | "build.rs")
+            || matches!(folder_name, "bin" | "tests" | "examples" | "benches");
+        let own = if is_root || stem == "mod" {
+            folder.clone()
+        } else {
+            join_path(&folder, stem)
+        };
+        let root = if is_root {
+            Some(folder.clone())
+        } else {
+            // The nearest folder with a `lib.rs` or `main.rs`
+            let mut current = Some(folder.as_str());
+            let mut root = None;
+            while let Some(candidate) = current {
+                if exists(&join_path(candidate, "lib.rs"))
+                    || exists(&join_path(candidate, "main.rs"))
+                {
+                    root = Some(candidate.to_string());
+                    break;
+                }
+                current = (!candidate.is_empty()).then(|| parent_path(candidate));
+            }
+            root
+        };
+        Module {
+            file: full_name.to_string(),
+            folder,
+            own,
+            root,
+        }
+    }
+
+    /// Resolve a path like `crate::a::b`, `super::super::a` or `self::a` used in the
+    /// (inline) module with the given folder. Other paths are only resolved if they start
+    /// with a child module, as they usually refer to other crates.
+    fn resolve_use(
+        &self,
+        own: &str,
+        path: &[&str],
+        exists: &dyn Fn(&str) -> bool,
+    ) -> Option<String> {
+        let mut segments = path.iter().copied().peekable();
+        let mut base = match *segments.peek()? {
+            "crate" => self.root.clone()?,
+            "self" => own.to_string(),
+            "super" => self.parent(own)?,
+            _ => {
+                let segments = segments.collect::<Vec<_>>();
+                return (1..=segments.len())
+                    .rev()
+                    .find_map(|length| self.find_module(own, &segments[..length], exists));
+            }
+        };
+        segments.next();
+        while segments.next_if_eq(&"super").is_some() {
+            base = self.parent(&base)?;
+        }
+        self.resolve_in(&base, &segments.collect::<Vec<_>>(), exists)
+    }
+
+    /// The folder of the parent module of the module with the given folder, `None` for the
+    /// crate root
+    fn parent(&self, own: &str) -> Option<String> {
+        if self.root.as_deref() == Some(own) || own.is_empty() {
+            None
+        } else {
+            Some(parent_path(own).to_string())
+        }
+    }
+
+    /// Find the file of the module with the given path below the module with the given folder
+    fn find_module(
+        &self,
+        own: &str,
+        segments: &[&str],
+        exists: &dyn Fn(&str) -> bool,
+    ) -> Option<String> {
+        let path = join_path(own, &segments.join("/"));
+        [format!("{}.rs", path), join_path(&path, "mod.rs")]
+            .into_iter()
+            .find(|c| exists(c))
+    }
+
+    /// Find the file of the longest prefix of the segments that is a module below the module
+    /// with the given folder, or the file of that module itself. The last segments are often
+    /// items (functions, types) instead of modules.
+    fn resolve_in(
+        &self,
+        own: &str,
+        segments: &[&str],
+        exists: &dyn Fn(&str) -> bool,
+    ) -> Option<String> {
+        for length in (1..=segments.len()).rev() {
+            if let Some(found) = self.find_module(own, &segments[..length], exists) {
+                return Some(found);
+            }
+        }
+        let candidates = if self.root.as_deref() == Some(own) {
+            if self.own == own && self.root.as_deref() == Some(self.folder.as_str()) {
+                vec![self.file.clone()]
+            } else {
+                vec![join_path(own, "lib.rs"), join_path(own, "main.rs")]
+            }
+        } else {
+            vec![format!("{}.rs", own), join_path(own, "mod.rs")]
+        };
+        candidates.into_iter().find(|c| exists(c))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The files the references in the given source resolve to, `None` for the ones that do
+    /// not resolve
+    fn resolved(path: &str, contents: &str, files: &[&str]) -> Vec<Option<String>> {
+        let exists = |p: &str| files.contains(&p);
+        let context = Context::new(&exists, &|_| None);
+        RustExtractor
+            .extract(path, contents)
+            .unwrap()
+            .iter()
+            .map(|reference| RustExtractor.resolve(path, reference, &context))
+            .collect()
+    }
+
+    fn some(paths: &[&str]) -> Vec<Option<String>> {
+        paths.iter().map(|p| Some(p.to_string())).collect()
+    }
+
+    /// The kinds, targets and scopes of the references in the given source
+    fn extracted(path: &str, contents: &str) -> Vec<(ReferenceKind, String, String)> {
+        RustExtractor
+            .extract(path, contents)
+            .unwrap()
+            .into_iter()
+            .map(|r| (r.kind, r.target, r.scope))
+            .collect()
+    }
+
+    fn imports(path: &str, contents: &str) -> Vec<String> {
+        extracted(path, contents)
+            .into_iter()
+            .map(|(_, target, _)| target)
+            .collect()
+    }
+
+    const FILES: [&str; 8] = [
+        "src/lib.rs",
+        "src/a.rs",
+        "src/a/c.rs",
+        "src/b/mod.rs",
+        "src/b/d.rs",
+        "src/inline/y.rs",
+        "src/inline/other.rs",
+        "src/p.rs",
+    ];
+
+    #[test]
+    fn grouped_and_nested_use_trees() {
+        assert_eq!(
+            imports("src/lib.rs", "use crate::a::{b, c::{d, e::f}, g::*};"),
+            vec![
+                "crate::a::b",
+                "crate::a::c::d",
+                "crate::a::c::e::f",
+                "crate::a::g"
+            ]
+        );
+        assert_eq!(imports("src/lib.rs", "use {a::b, c};"), vec!["a::b", "c"]);
+    }
+
+    #[test]
+    fn self_glob_and_renamed_imports() {
+        assert_eq!(
+            imports(
+                "src/lib.rs",
+                "use crate::a::{self, b as c};\nuse super::d::*;\nuse e as f;"
+            ),
+            vec!["crate::a", "crate::a::b", "super::d", "e"]
+        );
+    }
+
+    #[test]
+    fn visible_and_nested_imports() {
+        let source = concat!(
+            "pub(crate) use crate::a::b;\n",
+            "pub use self::c;\n",
+            "fn f() {\n",
+            "    use crate::d;\n",
+            "}\n",
+            "mod inner {\n",
+            "    use super::e;\n",
+            "}\n",
+        );
+        assert_eq!(
+            extracted("src/x.rs", source),
+            vec![
+                (
+                    ReferenceKind::Import,
+                    "crate::a::b".to_string(),
+                    "src/x".to_string()
+                ),
+                (
+                    ReferenceKind::Import,
+                    "self::c".to_string(),
+                    "src/x".to_string()
+                ),
+                (
+                    ReferenceKind::Import,
+                    "crate::d".to_string(),
+                    "src/x".to_string()
+                ),
+                (
+                    ReferenceKind::Import,
+                    "super::e".to_string(),
+                    "src/x/inner".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn includes_in_macro_arguments() {
+        let source = concat!(
+            "const A: &str = include_str!(\"a.txt\");\n",
+            "fn f() {\n",
+            "    println!(\"{}\", include_str!(\"../b.txt\"));\n",
+            "    let c = vec![include_bytes!(\"c.bin\").len()];\n",
+            "    std::include!(\"d.rs\");\n",
+            "}\n",
+        );
+        assert_eq!(
+            extracted("src/x.rs", source),
+            ["a.txt", "../b.txt", "c.bin", "d.rs"]
+                .iter()
+                .map(|t| (ReferenceKind::Include, t.to_string(), "src".to_string()))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            resolved("src/x.rs", source, &[]),
+            some(&["src/a.txt", "b.txt", "src/c.bin", "src/d.rs"])
+        );
+    }
+
+    #[test]
+    fn comments_and_strings_are_ignored() {
+        let source = concat!(
+            "// use crate::a;\n",
+            "/* mod b; include_str!(\"c.txt\") */\n",
+            "/// use crate::d;\n",
+            "const E: &str = \"use crate::e; mod f;\";\n",
+            "const G: &str = r#\"include_str!(\"g.txt\")\"#;\n",
+            "mod h;\n",
+        );
+        assert_eq!(
+            extracted("src/lib.rs", source),
+            vec![(ReferenceKind::Module, "h".to_string(), "src".to_string())]
+        );
+    }
+
+    #[test]
+    fn invalid_syntax() {
+        assert!(RustExtractor.extract("src/lib.rs", "fn (").is_err());
+    }
+
+    #[test]
+    fn modules_in_files_and_mod_rs() {
+        // A missing module resolves to the file itself, which is left out later
+        assert_eq!(
+            resolved("src/lib.rs", "mod a;\nmod b;\nmod missing;", &FILES),
+            some(&["src/a.rs", "src/b/mod.rs", "src/lib.rs"])
+        );
+        // The child modules of `a.rs` are in `a/`, the ones of `mod.rs` next to it
+        assert_eq!(
+            resolved("src/a.rs", "mod c;", &FILES),
+            some(&["src/a/c.rs"])
+        );
+        assert_eq!(
+            resolved("src/b/mod.rs", "mod d;", &FILES),
+            some(&["src/b/d.rs"])
+        );
+    }
+
+    #[test]
+    fn path_attributes() {
+        // Relative to the folder of the file, also for files that are not `mod.rs`
+        assert_eq!(
+            resolved("src/a.rs", "#[path = \"p.rs\"] mod q;", &FILES),
+            some(&["src/p.rs"])
+        );
+        // Inside an inline module relative to the folder of that module
+        let source = "mod inline {\n    #[path = \"other.rs\"]\n    mod x;\n    mod y;\n}";
+        assert_eq!(
+            resolved("src/lib.rs", source, &FILES),
+            some(&["src/inline/other.rs", "src/inline/y.rs"])
+        );
+    }
+
+    #[test]
+    fn use_paths_follow_the_module_tree() {
+        let source = concat!(
+            "use crate::a::c::Item;\n",
+            "use super::super::b::d;\n",
+            "use super::Sibling;\n",
+            "use crate::Root;\n",
+            "use std::collections::HashMap;\n",
+        );
+        assert_eq!(
+            resolved("src/a/c.rs", source, &FILES),
+            vec![
+                Some("src/a/c.rs".to_string()),
+                Some("src/b/d.rs".to_string()),
+                Some("src/a.rs".to_string()),
+                Some("src/lib.rs".to_string()),
+                None,
+            ]
+        );
+        // Above the crate root
+        assert_eq!(
+            resolved("src/a.rs", "use super::super::x;", &FILES),
+            vec![None]
+        );
+        // Paths starting with a child module, and `self` in an inline module
+        assert_eq!(
+            resolved(
+                "src/lib.rs",
+                "use b::d::f;\nmod inline { use self::y::g; }",
+                &FILES
+            ),
+            some(&["src/b/d.rs", "src/inline/y.rs"])
+        );
+    }
+
+    #[test]
+    fn crate_roots() {
+        let files = [
+            "src/bin/tool.rs",
+            "src/bin/helper.rs",
+            "tests/it.rs",
+            "tests/common/mod.rs",
+            "examples/demo.rs",
+            "examples/shared.rs",
+            "crates/x/src/lib.rs",
+            "crates/x/src/deep/util.rs",
+            "crates/x/src/other.rs",
+        ];
+        assert_eq!(
+            resolved("src/bin/tool.rs", "mod helper;", &files),
+            some(&["src/bin/helper.rs"])
+        );
+        assert_eq!(
+            resolved("tests/it.rs", "mod common;", &files),
+            some(&["tests/common/mod.rs"])
+        );
+        assert_eq!(
+            resolved("examples/demo.rs", "use crate::shared::f;", &files),
+            some(&["examples/shared.rs"])
+        );
+        // The nearest folder with a `lib.rs` is the root of the crate
+        assert_eq!(
+            resolved("crates/x/src/deep/util.rs", "use crate::other::f;", &files),
+            some(&["crates/x/src/other.rs"])
+        );
+    }
+}
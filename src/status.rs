@@ -0,0 +1,89 @@
+use crate::error::*;
+use crate::git;
+
+use std::collections::HashMap;
+use std::path::Path;
+
+/// The state of a file in the working tree and index compared to `HEAD`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// The file has changes that are not staged yet
+    Modified,
+    /// All changes to the file are staged
+    Staged,
+    /// The file is not tracked by git
+    Untracked,
+    /// The file has unresolved merge conflicts
+    Conflicted,
+}
+
+impl Status {
+    /// The name of the status, also used as class in the plot
+    pub fn name(&self) -> &'static str {
+        match self {
+            Status::Modified => "modified",
+            Status::Staged => "staged",
+            Status::Untracked => "untracked",
+            Status::Conflicted => "conflicted",
+        }
+    }
+
+    /// The labels and colours of all statuses to explain them in a legend. The labels tell
+    /// them apart from the changes of a diff or patch shown at the same time.
+    pub fn legend() -> Vec<(String, String)> {
+        [
+            ("modified in the working tree", "modified"),
+            ("staged", "staged"),
+            ("untracked", "untracked"),
+            ("conflicted", "conflicted"),
+        ]
+        .iter()
+        .map(|(label, name)| (label.to_string(), format!("var(--color-status-{})", name)))
+        .collect()
+    }
+}
+
+/// Get the status of every file below the given folder that differs from `HEAD`, by path
+/// relative to the folder. Files that are deleted in the working tree are left out.
+pub fn get_status(path: &Path) -> Result<HashMap<String, Status>> {
+    // The paths in the status are relative to the root of the repository
+    let prefix = git::run(path, &["rev-parse", "--show-prefix"])?;
+    let prefix = String::from_utf8_lossy(&prefix).trim().to_string();
+    let output = git::run(
+        path,
+        &[
+            "status",
+            "--porcelain=v1",
+            "-z",
+            "--untracked-files=all",
+            "--",
+            ".",
+        ],
+    )?;
+    let output = String::from_utf8_lossy(&output);
+    let mut fields = output.split('\0').filter(|f| !f.is_empty());
+    let mut statuses = HashMap::new();
+    while let Some(field) = fields.next() {
+        let (code, file) = match (field.get(..2), field.get(3..)) {
+            (Some(code), Some(file)) => (code, file),
+            _ => continue,
+        };
+        let mut code = code.chars();
+        let (index, tree) = (code.next().unwrap_or(' '), code.next().unwrap_or(' '));
+        if index == 'R' || index == 'C' {
+            // Renames and copies are followed by the original path
+            fields.next();
+        }
+        let status = match (index, tree) {
+            ('?', '?') => Status::Untracked,
+            ('U', _) | (_, 'U') | ('A', 'A') | ('D', 'D') => Status::Conflicted,
+            (_, 'D') | ('D', _) => continue,
+            (_, 'M') | (_, 'T') => Status::Modified,
+            _ => Status::Staged,
+        };
+        if let Some(file) = file.strip_prefix(prefix.as_str()) {
+            statuses.insert(file.to_string(), status);
+        }
+    }
+    Ok(statuses)
+}
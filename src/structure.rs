@@ -1,85 +1,510 @@
-use regex::Regex;
+use crate::cargo::{Package, Workspace};
+use crate::colour::*;
+use crate::diff::Change;
+use crate::error::*;
+use crate::filter::PathFilter;
+use crate::git;
+use crate::history::*;
+use crate::lockfile::Dependency;
+use crate::patch::FilePatch;
+use crate::refs::{find_refs, Context, Extractors};
+use crate::status::Status;
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::io::Read;
+use std::io;
 use std::path::Path;
 
-pub fn get_structure(path: &Path, ignore: &[&str]) -> Option<Item> {
-    if path.is_dir()
-        && !ignore
-            .iter()
-            .any(|d| Some(*d) == path.file_name().map(|s| s.to_str()).flatten())
-    {
-        Some(Item::Folder {
-            name: path.file_name().map(|s| s.to_str()).flatten()?.to_string(),
-            items: fs::read_dir(path).map_or(vec![], |r| {
-                r.filter_map(|p| p.ok())
-                    .map(|p| get_structure(&p.path(), ignore))
-                    .flatten()
-                    .collect()
-            }),
-        })
-    } else if path.is_file() {
-        if let Ok(meta) = path.metadata() {
-            //let matches = patterns.find_iter(&s);
-            let name = path
-                .file_name()
-                .map(|s| s.to_str())
-                .flatten()?
-                .trim()
-                .to_string();
-            Some(Item::File {
-                name,
-                full_name: path.to_str()?.trim().to_string(),
-                size: if meta.len() == 0 { 1 } else { meta.len() },
-                class: find_class(path),
-                refs: find_refs(path)
-                    .iter()
-                    .map(|s| s.trim().to_string())
-                    .collect(),
-            })
+/// A scanned repository together with the problems encountered on the way
+#[derive(Debug)]
+pub struct Scan {
+    /// The structure of the repository
+    pub root: Item,
+    /// Paths that were skipped or only partially scanned
+    pub warnings: Vec<Warning>,
+}
+
+/// Options controlling which paths are scanned, start from [`ScanOptions::default`]
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ScanOptions {
+    /// Glob patterns of files and folders to leave out, see [`PathFilter`] for the syntax
+    pub ignore: Vec<String>,
+    /// Glob patterns of files to show, if there is any pattern all other files are left
+    /// out as well as any folder that ends up empty, see [`PathFilter`] for the syntax
+    pub include: Vec<String>,
+    /// Leave out the `.git` folder and any path ignored by git, following the `.gitignore`
+    /// files in the repository, `.git/info/exclude` and the global excludes file
+    pub git_ignore: bool,
+    /// The extractors used to find the references between files
+    pub extractors: Extractors,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        ScanOptions {
+            ignore: Vec::new(),
+            include: Vec::new(),
+            git_ignore: true,
+            extractors: Extractors::default(),
+        }
+    }
+}
+
+/// Scan the given folder (or file), leaving out the paths excluded by the options.
+/// Problems with single paths inside the folder are collected as warnings, only
+/// when the path itself cannot be scanned an error is returned.
+pub fn get_structure(path: &Path, options: &ScanOptions) -> Result<Scan> {
+    let path = &path.canonicalize().map_err(|e| Error::io(path, e))?;
+    let meta = fs::metadata(path).map_err(|e| Error::io(path, e))?;
+    let name = folder_name(path);
+    let folder = if meta.is_dir() {
+        path.clone()
+    } else {
+        path.parent().unwrap_or(path).to_path_buf()
+    };
+    let exists = |p: &str| path_exists(&folder, p);
+    let read = |p: &str| fs::read_to_string(folder.join(p)).ok();
+    let context = Context::new(&exists, &read);
+    let mut scanner = Scanner {
+        options,
+        context: &context,
+        root: path,
+        ignore: PathFilter::new(&options.ignore)?,
+        include: PathFilter::new(&options.include)?,
+        git_ignores: Vec::new(),
+        warnings: Vec::new(),
+    };
+    if options.git_ignore {
+        scanner.git_ignores = get_outer_git_ignores(path, &mut scanner.warnings);
+    }
+    let root = if meta.is_dir() {
+        Item::Folder {
+            name,
+            items: scanner.get_items(path).map_err(|e| Error::io(path, e))?,
+            package: None,
+            dependencies: Vec::new(),
+        }
+    } else if meta.is_file() {
+        get_file(
+            path,
+            name.clone(),
+            name,
+            meta.len(),
+            &options.extractors,
+            &context,
+            &mut scanner.warnings,
+        )
+    } else {
+        return Err(Error::NotScannable(path.to_path_buf()));
+    };
+    Ok(Scan {
+        root,
+        warnings: scanner.warnings,
+    })
+}
+
+/// Scan the files of the given git revision (a commit, tag, branch or anything else git
+/// understands) below the given folder, by reading the git objects without checking the
+/// revision out. The ignore and include patterns are applied, the git ignore rules are
+/// not as only committed files are listed.
+pub fn get_revision_structure(path: &Path, revision: &str, options: &ScanOptions) -> Result<Scan> {
+    let path = &path.canonicalize().map_err(|e| Error::io(path, e))?;
+    if !path.is_dir() {
+        return Err(Error::NotScannable(path.to_path_buf()));
+    }
+    let name = folder_name(path);
+    let ignore = PathFilter::new(&options.ignore)?;
+    let include = PathFilter::new(&options.include)?;
+    let entries = git::list_tree(path, revision)?;
+    // The files and, ending with `/`, the folders, `/` is the scanned folder itself
+    let mut all_files = HashSet::from(["/".to_string()]);
+    for entry in &entries {
+        let mut folder = entry.path.as_str();
+        while let Some((parent, _)) = folder.rsplit_once('/') {
+            if !all_files.insert(format!("{}/", parent)) {
+                break;
+            }
+            folder = parent;
+        }
+        all_files.insert(entry.path.clone());
+    }
+    // The configuration files the extractors read are read in the same batch as the source
+    // files, they are often not shown themselves
+    let configs = entries
+        .iter()
+        .filter(|entry| options.extractors.reads(&entry.path))
+        .map(|entry| (entry.path.clone(), entry.id.clone()))
+        .collect::<Vec<_>>();
+    let ids = entries
+        .iter()
+        .map(|entry| (entry.path.clone(), entry.id.clone()))
+        .collect::<HashMap<_, _>>();
+    let entries = entries
+        .into_iter()
+        .filter(|entry| is_shown(&entry.path, &ignore, &include))
+        .collect::<Vec<_>>();
+    let sources = entries
+        .iter()
+        .filter(|entry| options.extractors.find(&entry.path).is_some())
+        .map(|entry| entry.id.as_str());
+    let batch = configs
+        .iter()
+        .map(|(_, id)| id.as_str())
+        .chain(sources)
+        .collect::<Vec<_>>();
+    let mut blobs = git::read_blobs(path, &batch)?.into_iter();
+    let configs = configs
+        .into_iter()
+        .zip(blobs.by_ref())
+        .filter_map(|((path, _), blob)| Some((path, String::from_utf8(blob).ok()?)))
+        .collect::<HashMap<_, _>>();
+    let exists = |p: &str| all_files.contains(p);
+    let read = |p: &str| match configs.get(p) {
+        Some(text) => Some(text.clone()),
+        None => {
+            let blob = git::read_blobs(path, &[ids.get(p)?]).ok()?.pop()?;
+            String::from_utf8(blob).ok()
+        }
+    };
+    let context = Context::new(&exists, &read);
+
+    let mut warnings = Vec::new();
+    let mut root = Item::Folder {
+        name,
+        items: Vec::new(),
+        package: None,
+        dependencies: Vec::new(),
+    };
+    for entry in entries {
+        let file_path = Path::new(&entry.path);
+        let refs = match options.extractors.find(&entry.path) {
+            Some(extractor) => find_refs(
+                extractor,
+                file_path,
+                &entry.path,
+                &blobs.next().unwrap_or_default(),
+                &context,
+                &mut warnings,
+            ),
+            None => Vec::new(),
+        };
+        root.insert_file(&entry.path, entry.size, refs);
+    }
+    Ok(Scan { root, warnings })
+}
+
+/// Check if a file at the given relative path passes the ignore and include patterns,
+/// a file in an ignored folder is never shown.
+pub(crate) fn is_shown(path: &str, ignore: &PathFilter, include: &PathFilter) -> bool {
+    let ignored = path
+        .match_indices('/')
+        .map(|(index, _)| &path[..index])
+        .chain(std::iter::once(path))
+        .any(|p| ignore.matched(p) == Some(true));
+    !ignored
+        && include
+            .matched_path_or_parents(path)
+            .unwrap_or(!include.has_positive())
+}
+
+/// The state kept while walking through the folders of a scan
+struct Scanner<'a> {
+    options: &'a ScanOptions,
+    /// The scanned folder as the extractors see it
+    context: &'a Context<'a>,
+    /// The scanned folder, all patterns are matched relative to it
+    root: &'a Path,
+    ignore: PathFilter,
+    include: PathFilter,
+    /// The git ignore rules that apply to the current folder, the most specific last
+    git_ignores: Vec<Gitignore>,
+    warnings: Vec<Warning>,
+}
+
+impl Scanner<'_> {
+    fn get_items(&mut self, path: &Path) -> io::Result<Vec<Item>> {
+        let entries = fs::read_dir(path)?;
+        let local_rules = if self.options.git_ignore {
+            get_git_ignore(path, &mut self.warnings)
         } else {
             None
+        };
+        let has_local_rules = local_rules.is_some();
+        self.git_ignores.extend(local_rules);
+
+        let mut items = Vec::new();
+        for entry in entries {
+            match entry {
+                Ok(entry) => {
+                    if let Some(item) = self.get_item(&entry.path()) {
+                        items.push(item)
+                    }
+                }
+                Err(e) => self
+                    .warnings
+                    .push(Warning::new(path, WarningKind::Unreadable(e.to_string()))),
+            }
         }
-    } else {
-        None
-    }
-}
-
-fn find_refs(path: &Path) -> Vec<String> {
-    if matches!(path.extension().map(|s| s.to_str()).flatten(), Some("rs")) {
-        let mut s = String::new();
-        let _ = fs::File::open(path)
-            .unwrap()
-            .read_to_string(&mut s)
-            .unwrap();
-        let patterns = Regex::new(
-            r"(?:use crate::([^;]*?)(?:::\*)?(?:::\{[^;]\})?;)|(?:use super::([^;]*?)(?:::\*)?(?:::\{[^;]\})?;)|(?:include_str!\(([^\)]*)\))|(?:mod ([^;{}]*);)",
-        ).unwrap();
-        patterns
-            .captures_iter(&s)
-            .filter_map(|c| {
-                if let Some(r) = c.get(1) {
-                    Some(r.as_str().replace("::", "/") + ".rs")
-                } else if let Some(r) = c.get(2) {
-                    Some(r.as_str().replace("::", "/") + ".rs")
-                } else if let Some(r) = c.get(3) {
-                    Some(r.as_str().replace('"', ""))
-                } else if let Some(r) = c.get(4) {
-                    Some(r.as_str().to_string() + ".rs")
+
+        if has_local_rules {
+            self.git_ignores.pop();
+        }
+        Ok(items)
+    }
+
+    fn get_item(&mut self, path: &Path) -> Option<Item> {
+        let name = match path.file_name().and_then(|s| s.to_str()) {
+            Some(name) => name.to_string(),
+            None => {
+                self.warnings
+                    .push(Warning::new(path, WarningKind::InvalidName));
+                return None;
+            }
+        };
+        let meta = match fs::metadata(path) {
+            Ok(meta) => meta,
+            Err(e) => {
+                let kind = if path.is_symlink() {
+                    WarningKind::Unsupported
                 } else {
+                    WarningKind::Unreadable(e.to_string())
+                };
+                self.warnings.push(Warning::new(path, kind));
+                return None;
+            }
+        };
+        if self.options.git_ignore
+            && ((meta.is_dir() && name == ".git")
+                || is_git_ignored(path, meta.is_dir(), &self.git_ignores))
+        {
+            return None;
+        }
+        let relative = relative_name(self.root, path);
+        if self.ignore.matched(&relative) == Some(true) {
+            return None;
+        }
+        if meta.is_dir() {
+            // Folders without any shown files are left out, like git does
+            match self.get_items(path) {
+                Ok(items) if items.is_empty() => None,
+                Ok(items) => Some(Item::Folder {
+                    name,
+                    items,
+                    package: None,
+                    dependencies: Vec::new(),
+                }),
+                Err(e) => {
+                    self.warnings
+                        .push(Warning::new(path, WarningKind::Unreadable(e.to_string())));
                     None
                 }
-            })
-            .collect()
+            }
+        } else if meta.is_file() {
+            let included = self
+                .include
+                .matched_path_or_parents(&relative)
+                .unwrap_or(!self.include.has_positive());
+            if included {
+                Some(get_file(
+                    path,
+                    name,
+                    relative,
+                    meta.len(),
+                    &self.options.extractors,
+                    self.context,
+                    &mut self.warnings,
+                ))
+            } else {
+                None
+            }
+        } else {
+            self.warnings
+                .push(Warning::new(path, WarningKind::Unsupported));
+            None
+        }
+    }
+}
+
+/// Get the path relative to the root of the scan, using `/` as separator
+fn relative_name(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Join a relative path to a folder, both relative to the scanned folder, resolving `.`
+/// and `..`
+pub(crate) fn join_path(folder: &str, path: &str) -> String {
+    if folder.is_empty() {
+        normalize_path(path)
     } else {
-        vec![]
+        normalize_path(&format!("{}/{}", folder, path))
+    }
+}
+
+/// Remove `.` and resolve `..` in a relative path with `/` or `\` as separators
+pub(crate) fn normalize_path(path: &str) -> String {
+    let mut components = Vec::new();
+    for component in path.split(['/', '\\']) {
+        match component {
+            "" | "." => {}
+            ".." => {
+                components.pop();
+            }
+            c => components.push(c),
+        }
+    }
+    components.join("/")
+}
+
+/// Check if there is a file at the given path relative to the folder, or a folder when the
+/// path ends with `/`
+fn path_exists(folder: &Path, path: &str) -> bool {
+    match path.strip_suffix('/') {
+        Some(path) => folder.join(path).is_dir(),
+        None => folder.join(path).is_file(),
+    }
+}
+
+/// The name of a scanned folder or file shown at the root, the whole path for `/`
+pub(crate) fn folder_name(path: &Path) -> String {
+    path.file_name().map_or_else(
+        || path.display().to_string(),
+        |s| s.to_string_lossy().to_string(),
+    )
+}
+
+/// The folder containing the given relative path, empty for the scanned folder itself
+pub(crate) fn parent_path(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(parent, _)| parent)
+}
+
+/// Get the git ignore rules that apply to the given path from outside of it: the global
+/// excludes file, `.git/info/exclude` and the `.gitignore` files in the folders between
+/// the root of the repository and the path. The most specific rules are placed last.
+fn get_outer_git_ignores(path: &Path, warnings: &mut Vec<Warning>) -> Vec<Gitignore> {
+    let repository = path
+        .ancestors()
+        .find(|p| p.join(".git").exists())
+        .unwrap_or(path);
+    let mut git_ignores = Vec::new();
+    let (global, error) = GitignoreBuilder::new(repository).build_global();
+    if let Some(e) = error {
+        warnings.push(Warning::new(
+            repository,
+            WarningKind::InvalidPattern(e.to_string()),
+        ));
+    }
+    git_ignores.push(global);
+    let exclude = repository.join(".git").join("info").join("exclude");
+    if exclude.is_file() {
+        let mut builder = GitignoreBuilder::new(repository);
+        if let Some(e) = builder.add(&exclude) {
+            warnings.push(Warning::new(
+                &exclude,
+                WarningKind::InvalidPattern(e.to_string()),
+            ));
+        }
+        match builder.build() {
+            Ok(rules) => git_ignores.push(rules),
+            Err(e) => warnings.push(Warning::new(
+                &exclude,
+                WarningKind::InvalidPattern(e.to_string()),
+            )),
+        }
+    }
+    let mut outer = path
+        .ancestors()
+        .skip(1)
+        .take_while(|p| p.starts_with(repository))
+        .collect::<Vec<_>>();
+    outer.reverse();
+    for folder in outer {
+        if let Some(rules) = get_git_ignore(folder, warnings) {
+            git_ignores.push(rules);
+        }
+    }
+    git_ignores
+}
+
+/// Get the rules of the `.gitignore` file in the given folder, if there is one
+fn get_git_ignore(folder: &Path, warnings: &mut Vec<Warning>) -> Option<Gitignore> {
+    let file = folder.join(".gitignore");
+    if !file.is_file() {
+        return None;
+    }
+    let (rules, error) = Gitignore::new(&file);
+    if let Some(e) = error {
+        warnings.push(Warning::new(
+            &file,
+            WarningKind::InvalidPattern(e.to_string()),
+        ));
+    }
+    Some(rules)
+}
+
+/// Check if the given path is ignored by git, the last (most specific) rules that
+/// match the path decide.
+fn is_git_ignored(path: &Path, is_dir: bool, git_ignores: &[Gitignore]) -> bool {
+    for rules in git_ignores.iter().rev() {
+        match rules.matched(path, is_dir) {
+            Match::Ignore(_) => return true,
+            Match::Whitelist(_) => return false,
+            Match::None => (),
+        }
+    }
+    false
+}
+
+fn get_file(
+    path: &Path,
+    name: String,
+    full_name: String,
+    size: u64,
+    extractors: &Extractors,
+    context: &Context,
+    warnings: &mut Vec<Warning>,
+) -> Item {
+    let refs = match extractors.find(&full_name) {
+        Some(extractor) => match fs::read(path) {
+            Ok(contents) => find_refs(extractor, path, &full_name, &contents, context, warnings),
+            Err(e) => {
+                warnings.push(Warning::new(path, WarningKind::Unreadable(e.to_string())));
+                Vec::new()
+            }
+        },
+        None => Vec::new(),
+    };
+    make_file(path, name, full_name, size, refs)
+}
+
+/// Create a file item with the given references, see [`find_refs`] to find them
+fn make_file(path: &Path, name: String, full_name: String, size: u64, refs: Vec<String>) -> Item {
+    Item::File {
+        name: name.trim().to_string(),
+        size: if size == 0 { 1 } else { size },
+        class: find_class(path),
+        full_name,
+        refs,
+        history: None,
+        change: None,
+        lines: None,
+        status: None,
     }
 }
 
 fn find_class(path: &Path) -> FileType {
     path.extension().map_or(FileType::Unknown, |n| {
         n.to_str().map_or(FileType::Unknown, |t| match t {
-            "rs" | "cs" | "js" | "ts" | "r" | "cpp" | "py" => FileType::Code,
+            "rs" | "cs" | "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "mts" | "cts" | "r"
+            | "c" | "cc" | "cpp" | "cxx" | "h" | "hh" | "hpp" | "hxx" | "py" | "go" | "java"
+            | "kt" | "kts" => FileType::Code,
             "csv" | "tsv" | "xlsx" | "xls" | "fasta" => FileType::Data,
             "yaml" | "toml" | "lock" => FileType::Configuration,
             _ => FileType::Unknown,
@@ -89,16 +514,31 @@ fn find_class(path: &Path) -> FileType {
 
 #[derive(Debug)]
 pub enum Item {
+    #[non_exhaustive]
     File {
         name: String,
         full_name: String,
         size: u64,
         class: FileType,
+        /// The files and folders the file references, the empty path is the scanned folder
         refs: Vec<String>,
+        /// The git history, only present when added with [`Item::add_history`]
+        history: Option<Box<FileHistory>>,
+        /// The change compared to an older revision, only present in a diff
+        change: Option<Change>,
+        /// The number of lines added and removed by a patch, only present when a patch is shown
+        lines: Option<(u64, u64)>,
+        /// The state in the working tree, only present when added with [`Item::add_status`]
+        status: Option<Status>,
     },
+    #[non_exhaustive]
     Folder {
         name: String,
         items: Vec<Item>,
+        /// The crate in this folder, only present when added with [`Item::add_workspace`]
+        package: Option<Package>,
+        /// The external packages used by this folder, see [`Item::add_dependencies`]
+        dependencies: Vec<Dependency>,
     },
 }
 
@@ -110,18 +550,111 @@ pub enum FileType {
     Unknown,
 }
 
+impl FileType {
+    pub fn colour(&self) -> &'static str {
+        match self {
+            FileType::Code => "var(--color-primary)",       //purple
+            FileType::Data => "var(--color-primary-shade)", // green
+            FileType::Configuration => "var(--color-secondary)", // yellow
+            FileType::Unknown => "var(--color-tertiary)",   // blue
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FileType::Code => "Code",
+            FileType::Data => "Data",
+            FileType::Configuration => "Configuration",
+            FileType::Unknown => "Unknown",
+        }
+    }
+}
+
 impl Item {
     pub fn size(&self) -> f64 {
+        self.size_by(SizeMode::Bytes)
+    }
+
+    /// The size of this item when showing the given metric, never less than one for a file
+    pub fn size_by(&self, mode: SizeMode) -> f64 {
         match self {
-            Item::File { size, .. } => *size as f64,
+            Item::File { size, history, .. } => match mode {
+                SizeMode::Bytes => *size as f64,
+                SizeMode::Churn => history.as_ref().map_or(0, |h| h.churn()) as f64 + 1.0,
+            },
+            Item::Folder { items, .. } if items.is_empty() => 0.0,
             Item::Folder { items, .. } => {
-                let sum = items.iter().fold(0.0, |acc, item| acc + item.size());
+                let sum = items.iter().fold(0.0, |acc, item| acc + item.size_by(mode));
                 let len = items.len() as f64;
                 25.0_f64.powf(len * 1.10) * sum * (sum / len)
             }
         }
     }
 
+    /// The value shown for this file in the given colour mode, `None` for folders and
+    /// for modes that do not show a value
+    pub fn metric(&self, mode: ColourMode) -> Option<f64> {
+        match self {
+            Item::File { history, .. } => match mode {
+                ColourMode::Type => None,
+                ColourMode::Commits => Some(history.as_ref().map_or(0, |h| h.commits) as f64),
+                ColourMode::Churn => Some(history.as_ref().map_or(0, |h| h.churn()) as f64),
+                ColourMode::Age => history.as_ref()?.last_modified.map(|t| t as f64),
+                ColourMode::Created => history.as_ref()?.created.map(|t| t as f64),
+                ColourMode::Author => None,
+                ColourMode::Hotspot => Some(history.as_ref().map_or(0, |h| h.hotspot()) as f64),
+            },
+            Item::Folder { .. } => None,
+        }
+    }
+
+    /// Add the history of every file, files that are not in the history get an empty one
+    pub fn add_history(&mut self, history: &History) {
+        match self {
+            Item::File {
+                full_name,
+                history: file_history,
+                ..
+            } => {
+                *file_history = Some(Box::new(
+                    history.files.get(full_name).cloned().unwrap_or_default(),
+                ))
+            }
+            Item::Folder { items, .. } => {
+                for item in items {
+                    item.add_history(history)
+                }
+            }
+        }
+    }
+
+    /// The given number of files with the highest hotspot score, highest first, see
+    /// [`FileHistory::hotspot`]. Files without history or a score of zero are left out.
+    pub fn hotspots(&self, count: usize) -> Vec<(&str, &FileHistory)> {
+        let mut files = Vec::new();
+        self.collect_histories(&mut files);
+        files.retain(|(_, history)| history.hotspot() > 0);
+        files.sort_by(|a, b| b.1.hotspot().cmp(&a.1.hotspot()).then_with(|| a.0.cmp(b.0)));
+        files.truncate(count);
+        files
+    }
+
+    fn collect_histories<'a>(&'a self, files: &mut Vec<(&'a str, &'a FileHistory)>) {
+        match self {
+            Item::File {
+                full_name,
+                history: Some(history),
+                ..
+            } => files.push((full_name, history)),
+            Item::File { .. } => {}
+            Item::Folder { items, .. } => {
+                for item in items {
+                    item.collect_histories(files)
+                }
+            }
+        }
+    }
+
     pub fn files(&self) -> i32 {
         match self {
             Item::File { .. } => 1,
@@ -129,15 +662,302 @@ impl Item {
         }
     }
 
-    pub fn colour(&self) -> &str {
+    pub fn colour(&self, scheme: &ColourScheme) -> String {
+        scheme.colour(self)
+    }
+
+    /// Mark this file with the given change
+    pub fn set_change(&mut self, new_change: Change) {
+        if let Item::File { change, .. } = self {
+            *change = Some(new_change)
+        }
+    }
+
+    /// The change of this file compared to an older revision, if any
+    pub fn change(&self) -> Option<&Change> {
+        match self {
+            Item::File { change, .. } => change.as_ref(),
+            Item::Folder { .. } => None,
+        }
+    }
+
+    /// Mark every file with its change, taking the changes out of the map by path
+    pub(crate) fn add_changes(&mut self, changes: &mut HashMap<String, Change>) {
+        match self {
+            Item::File {
+                full_name, change, ..
+            } => {
+                if let Some(c) = changes.remove(full_name.as_str()) {
+                    *change = Some(c)
+                }
+            }
+            Item::Folder { items, .. } => {
+                for item in items {
+                    item.add_changes(changes)
+                }
+            }
+        }
+    }
+
+    /// Set the number of lines added and removed by a patch
+    pub fn set_lines(&mut self, added: u64, removed: u64) {
+        if let Item::File { lines, .. } = self {
+            *lines = Some((added, removed))
+        }
+    }
+
+    /// Mark every file with its change and line counts from a patch, taking the files out of
+    /// the map by path
+    pub(crate) fn add_patch(&mut self, files: &mut HashMap<String, FilePatch>) {
+        match self {
+            Item::File {
+                full_name,
+                change,
+                lines,
+                ..
+            } => {
+                if let Some(file) = files.remove(full_name.as_str()) {
+                    *change = Some(file.change);
+                    *lines = Some((file.lines_added, file.lines_removed));
+                }
+            }
+            Item::Folder { items, .. } => {
+                for item in items {
+                    item.add_patch(files)
+                }
+            }
+        }
+    }
+
+    /// Mark the folder of every crate in the workspace with its crate
+    pub fn add_workspace(&mut self, workspace: &Workspace) {
+        self.for_each_folder("", &mut |folder, path| {
+            if let Item::Folder { package, .. } = folder {
+                *package = workspace.packages.iter().find(|p| p.path == path).cloned();
+            }
+        })
+    }
+
+    /// Give every folder the external packages it uses
+    pub fn add_dependencies(&mut self, all: &[Dependency]) {
+        self.for_each_folder("", &mut |folder, path| {
+            if let Item::Folder { dependencies, .. } = folder {
+                *dependencies = all
+                    .iter()
+                    .filter(|d| d.users.iter().any(|u| u == path))
+                    .cloned()
+                    .collect();
+            }
+        })
+    }
+
+    /// Call `visit` with this folder and every folder below it, together with their paths
+    /// relative to the scanned folder, given this folder is at `path`
+    fn for_each_folder(&mut self, path: &str, visit: &mut dyn FnMut(&mut Item, &str)) {
+        if let Item::File { .. } = self {
+            return;
+        }
+        visit(self, path);
+        if let Item::Folder { items, .. } = self {
+            for item in items {
+                let path = match item {
+                    Item::Folder { name, .. } if path.is_empty() => name.clone(),
+                    Item::Folder { name, .. } => format!("{}/{}", path, name),
+                    Item::File { .. } => continue,
+                };
+                item.for_each_folder(&path, visit)
+            }
+        }
+    }
+
+    /// The external packages used by this folder, if they are added
+    pub fn dependencies(&self) -> &[Dependency] {
+        match self {
+            Item::Folder { dependencies, .. } => dependencies,
+            Item::File { .. } => &[],
+        }
+    }
+
+    /// The crate in this folder, if the workspace is added
+    pub fn package(&self) -> Option<&Package> {
+        match self {
+            Item::Folder { package, .. } => package.as_ref(),
+            Item::File { .. } => None,
+        }
+    }
+
+    /// The state of this file in the working tree, if it differs from `HEAD`
+    pub fn status(&self) -> Option<Status> {
+        match self {
+            Item::File { status, .. } => *status,
+            Item::Folder { .. } => None,
+        }
+    }
+
+    /// Add the working tree status of every file, see [`crate::get_status`]
+    pub fn add_status(&mut self, statuses: &HashMap<String, Status>) {
+        match self {
+            Item::File {
+                full_name, status, ..
+            } => *status = statuses.get(full_name.as_str()).copied(),
+            Item::Folder { items, .. } => {
+                for item in items {
+                    item.add_status(statuses)
+                }
+            }
+        }
+    }
+
+    /// The text shown on this item in the plot
+    pub fn label(&self) -> String {
+        if let Item::File {
+            lines: Some((added, removed)),
+            ..
+        } = self
+        {
+            return format!("{} (+{} -{})", self.name(), added, removed);
+        }
+        match self.change() {
+            Some(Change::Modified { size_delta }) | Some(Change::Renamed { size_delta, .. }) => {
+                format!("{} ({:+} B)", self.name(), size_delta)
+            }
+            _ => self.name().to_string(),
+        }
+    }
+
+    /// The author with the most commits to this file, if the history is added
+    pub fn primary_author(&self) -> Option<&str> {
+        match self {
+            Item::File {
+                history: Some(history),
+                ..
+            } => history.primary_author().map(|(author, _)| author),
+            _ => None,
+        }
+    }
+
+    /// A description of this item, shown when hovering over it
+    pub fn description(&self) -> String {
+        let change = match self.change() {
+            Some(Change::Renamed { from, size_delta }) => {
+                format!("\nrenamed from {} ({:+} bytes)", from, size_delta)
+            }
+            Some(Change::Modified { size_delta }) => format!("\nmodified ({:+} bytes)", size_delta),
+            Some(c) => format!("\n{}", c.name()),
+            None => String::new(),
+        };
+        let change = match self {
+            Item::File {
+                lines: Some((added, removed)),
+                ..
+            } => format!("{}, +{} -{} lines", change, added, removed),
+            _ => change,
+        };
+        let change = match self.status() {
+            Some(status) => format!("{}\n{} in the working tree", change, status.name()),
+            None => change,
+        };
+        let text = match self {
+            Item::File {
+                full_name, history, ..
+            } => match history {
+                Some(h) => {
+                    let mut text = format!(
+                        "{}\n{} commits, +{} -{}, hotspot score {}",
+                        full_name,
+                        h.commits,
+                        h.lines_added,
+                        h.lines_removed,
+                        h.hotspot()
+                    );
+                    if let (Some(last_modified), Some(created)) = (h.last_modified, h.created) {
+                        text += &format!(
+                            "\nlast changed {}, created {}",
+                            format_date(last_modified),
+                            format_date(created)
+                        );
+                    }
+                    if let Some((author, commits)) = h.primary_author() {
+                        text += &format!(
+                            "\nprimary author {} ({} of {} commits)",
+                            author, commits, h.commits
+                        );
+                    }
+                    text
+                }
+                None => full_name.to_string(),
+            },
+            Item::Folder {
+                name,
+                package: Some(package),
+                ..
+            } => format!("{}\ncrate {}", name, package.name),
+            Item::Folder { name, .. } => name.to_string(),
+        };
+        text + &change
+    }
+
+    /// Add the file at the given `/` separated path below this folder, creating the
+    /// folders on the way as needed, and get it. `None` if this is a file.
+    pub(crate) fn insert(&mut self, path: &str, file: Item) -> Option<&mut Item> {
         match self {
-            Item::File { class, .. } => match class {
-                FileType::Code => "var(--color-primary)",       //purple
-                FileType::Data => "var(--color-primary-shade)", // green
-                FileType::Configuration => "var(--color-secondary)", // yellow
-                FileType::Unknown => "var(--color-tertiary)",   // blue
+            Item::Folder { items, .. } => match path.split_once('/') {
+                Some((folder, rest)) => {
+                    let index = items
+                        .iter()
+                        .position(|i| matches!(i, Item::Folder { name, .. } if name == folder))
+                        .unwrap_or_else(|| {
+                            items.push(Item::Folder {
+                                name: folder.to_string(),
+                                items: Vec::new(),
+                                package: None,
+                                dependencies: Vec::new(),
+                            });
+                            items.len() - 1
+                        });
+                    items[index].insert(rest, file)
+                }
+                None => {
+                    items.push(file);
+                    items.last_mut()
+                }
             },
-            Item::Folder { .. } => "var(--color-light)", // grey
+            Item::File { .. } => None,
+        }
+    }
+
+    /// Insert a file without history at the given `/` separated path below this folder, see
+    /// [`Item::insert`]
+    pub(crate) fn insert_file(
+        &mut self,
+        path: &str,
+        size: u64,
+        refs: Vec<String>,
+    ) -> Option<&mut Item> {
+        let name = path.rsplit('/').next().unwrap_or_default().to_string();
+        self.insert(
+            path,
+            make_file(Path::new(path), name, path.to_string(), size, refs),
+        )
+    }
+
+    /// Get the file or folder at the given `/` separated path below this folder
+    pub(crate) fn get(&self, path: &str) -> Option<&Item> {
+        let mut item = self;
+        for name in path.split('/').filter(|n| !n.is_empty()) {
+            item = match item {
+                Item::Folder { items, .. } => items.iter().find(|i| i.name() == name)?,
+                Item::File { .. } => return None,
+            };
+        }
+        Some(item)
+    }
+
+    /// The path of a file relative to the scanned folder, or the name of a folder
+    pub fn full_name(&self) -> &str {
+        match self {
+            Item::File { full_name, .. } => full_name,
+            Item::Folder { name, .. } => name,
         }
     }
 
@@ -148,3 +968,50 @@ impl Item {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Create a folder with the given files in the temporary folder, removing an older one
+    fn create(name: &str, files: &[&str]) -> std::path::PathBuf {
+        let root = std::env::temp_dir().join(format!("git-cuttle-{}", name));
+        let _ = fs::remove_dir_all(&root);
+        for file in files {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "x").unwrap();
+        }
+        root
+    }
+
+    #[test]
+    fn empty_folders_have_no_size() {
+        let folder = Item::Folder {
+            name: "a".to_string(),
+            items: Vec::new(),
+            package: None,
+            dependencies: Vec::new(),
+        };
+        assert_eq!(folder.size_by(SizeMode::Bytes), 0.0);
+        assert_eq!(folder.size_by(SizeMode::Churn), 0.0);
+    }
+
+    #[test]
+    fn folders_without_shown_files_are_left_out() {
+        let root = create("empty-folders", &["src/a.txt", "ign/z.o", "ign/deep/y.o"]);
+        fs::create_dir_all(root.join("empty")).unwrap();
+        let options = ScanOptions {
+            ignore: vec!["*.o".to_string()],
+            ..ScanOptions::default()
+        };
+        let scan = get_structure(&root, &options).unwrap();
+        let names = match &scan.root {
+            Item::Folder { items, .. } => items.iter().map(|i| i.name()).collect::<Vec<_>>(),
+            Item::File { .. } => Vec::new(),
+        };
+        assert_eq!(names, vec!["src"]);
+        assert!(scan.root.size_by(SizeMode::Bytes).is_finite());
+        fs::remove_dir_all(root).unwrap();
+    }
+}
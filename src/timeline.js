@@ -0,0 +1,46 @@
+var timeline_files = null;
+var timeline_frame = 0;
+var timeline_timer = null;
+
+function timeline_show(index) {
+    if (timeline_files == null) {
+        timeline_files = {};
+        document.querySelectorAll(".file[data-path]").forEach(f => timeline_files[f.dataset.path] = f);
+    }
+    timeline_frame = Number(index);
+    var frame = timeline_frames[timeline_frame];
+    timeline_paths.forEach((path, i) => {
+        var file = timeline_files[path];
+        if (file == undefined) {
+            return;
+        }
+        var radius = frame.radii[i];
+        file.querySelector("circle").style.setProperty("r", radius);
+        file.style.setProperty("opacity", radius > 0 ? 1 : 0);
+    });
+    document.getElementById("timeline-slider").value = timeline_frame;
+    document.getElementById("timeline-label").textContent = frame.label;
+}
+
+function timeline_play() {
+    var button = document.getElementById("timeline-play");
+    if (timeline_timer != null) {
+        clearInterval(timeline_timer);
+        timeline_timer = null;
+        button.textContent = "Play";
+        return;
+    }
+    if (timeline_frame >= timeline_frames.length - 1) {
+        timeline_show(0);
+    }
+    button.textContent = "Pause";
+    timeline_timer = setInterval(() => {
+        if (timeline_frame >= timeline_frames.length - 1) {
+            timeline_play();
+        } else {
+            timeline_show(timeline_frame + 1);
+        }
+    }, 500);
+}
+
+window.addEventListener("load", () => timeline_show(timeline_frames.length - 1));
@@ -0,0 +1,218 @@
+use crate::error::*;
+use crate::filter::PathFilter;
+use crate::git;
+use crate::history::civil_date;
+use crate::structure::*;
+
+use std::collections::HashMap;
+use std::path::Path;
+
+/// How far apart the frames of a timeline are, periods are in UTC
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FrameStep {
+    /// A frame every given number of commits
+    Commits(usize),
+    /// A frame for the last commit in every period of the given number of days
+    Days(u64),
+    /// A frame for the last commit in every week, starting on Monday
+    Week,
+    /// A frame for the last commit in every calendar month
+    Month,
+}
+
+/// The state of the repository at a single commit
+#[derive(Debug, Clone)]
+pub struct Frame {
+    /// The id of the commit
+    pub commit: String,
+    /// The commit time, in seconds since the epoch
+    pub time: i64,
+    /// The size of every file present at this commit, by path relative to the folder
+    pub sizes: HashMap<String, u64>,
+}
+
+/// The history of a repository as a series of frames
+#[derive(Debug)]
+pub struct Timeline {
+    /// All files that are present in any frame, with the largest size they ever had, so
+    /// that one layout can be used for all frames
+    pub root: Item,
+    /// The frames from old to new
+    pub frames: Vec<Frame>,
+}
+
+/// Get the timeline of the given folder, following the first parent of every commit up to
+/// the given revision or `HEAD`. The ignore and include patterns are applied to every frame.
+/// There is always at least one frame, a folder without commits is an error.
+pub fn get_timeline(
+    path: &Path,
+    revision: Option<&str>,
+    step: FrameStep,
+    options: &ScanOptions,
+) -> Result<Timeline> {
+    let path = &path.canonicalize().map_err(|e| Error::io(path, e))?;
+    let name = folder_name(path);
+    let ignore = PathFilter::new(&options.ignore)?;
+    let include = PathFilter::new(&options.include)?;
+
+    let output = git::run(
+        path,
+        &[
+            "log",
+            "--first-parent",
+            "--reverse",
+            "--format=%H %ct",
+            "--end-of-options",
+            revision.unwrap_or("HEAD"),
+            "--",
+            ".",
+        ],
+    )?;
+    let commits = String::from_utf8_lossy(&output)
+        .lines()
+        .filter_map(|line| {
+            let (commit, time) = line.split_once(' ')?;
+            Some((commit.to_string(), time.parse::<i64>().ok()?))
+        })
+        .collect::<Vec<_>>();
+
+    let selected = select_commits(&commits, step);
+    if selected.is_empty() {
+        return Err(Error::NoCommits(path.to_path_buf()));
+    }
+    let mut frames = Vec::new();
+    let mut largest: HashMap<String, u64> = HashMap::new();
+    for (commit, time) in selected {
+        let sizes = git::list_tree(path, &commit)?
+            .into_iter()
+            .filter(|entry| is_shown(&entry.path, &ignore, &include))
+            .map(|entry| (entry.path, entry.size))
+            .collect::<HashMap<_, _>>();
+        for (file, size) in &sizes {
+            let max = largest.entry(file.clone()).or_default();
+            *max = (*max).max(*size);
+        }
+        frames.push(Frame {
+            commit,
+            time,
+            sizes,
+        });
+    }
+
+    let mut files = largest.into_iter().collect::<Vec<_>>();
+    files.sort();
+    let mut root = Item::Folder {
+        name,
+        items: Vec::new(),
+        package: None,
+        dependencies: Vec::new(),
+    };
+    for (file, size) in files {
+        root.insert_file(&file, size, Vec::new());
+    }
+    Ok(Timeline { root, frames })
+}
+
+/// Select the commits (from old to new) to make a frame of, the newest commit is always included
+fn select_commits(commits: &[(String, i64)], step: FrameStep) -> Vec<(String, i64)> {
+    let mut selected = Vec::new();
+    match step {
+        FrameStep::Commits(n) => {
+            let n = n.max(1);
+            for (index, commit) in commits.iter().enumerate() {
+                if (commits.len() - 1 - index).is_multiple_of(n) {
+                    selected.push(commit.clone());
+                }
+            }
+        }
+        FrameStep::Days(_) | FrameStep::Week | FrameStep::Month => {
+            for (index, commit) in commits.iter().enumerate() {
+                let next = commits.get(index + 1);
+                if next.is_none_or(|(_, time)| period(*time, step) != period(commit.1, step)) {
+                    selected.push(commit.clone());
+                }
+            }
+        }
+    }
+    selected
+}
+
+/// The number of the period of a step a time is in, counted from the epoch
+fn period(time: i64, step: FrameStep) -> i64 {
+    let days = time.div_euclid(86400);
+    match step {
+        FrameStep::Commits(_) => 0,
+        FrameStep::Days(n) => days.div_euclid(n.max(1) as i64),
+        // The epoch was on a Thursday
+        FrameStep::Week => (days + 3).div_euclid(7),
+        FrameStep::Month => {
+            let (year, month, _) = civil_date(time);
+            year * 12 + month - 1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Commits with the given names and times, from old to new
+    fn commits(dates: &[(&str, i64)]) -> Vec<(String, i64)> {
+        dates
+            .iter()
+            .map(|(name, time)| (name.to_string(), *time))
+            .collect()
+    }
+
+    fn selected(commits: &[(String, i64)], step: FrameStep) -> Vec<String> {
+        select_commits(commits, step)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    #[test]
+    fn every_n_commits_ends_with_the_newest() {
+        let commits = commits(&[("a", 1), ("b", 2), ("c", 3), ("d", 4), ("e", 5)]);
+        assert_eq!(selected(&commits, FrameStep::Commits(1)).len(), 5);
+        assert_eq!(
+            selected(&commits, FrameStep::Commits(2)),
+            vec!["a", "c", "e"]
+        );
+        assert_eq!(selected(&commits, FrameStep::Commits(10)), vec!["e"]);
+        assert!(selected(&[], FrameStep::Commits(1)).is_empty());
+    }
+
+    #[test]
+    fn weeks_start_on_monday() {
+        // 2024-01-07 was a Sunday
+        let sunday = 1704585600;
+        let commits = commits(&[
+            ("saturday", sunday - 86400),
+            ("sunday", sunday + 23 * 3600),
+            ("monday", sunday + 86400),
+            ("next sunday", sunday + 7 * 86400),
+        ]);
+        assert_eq!(
+            selected(&commits, FrameStep::Week),
+            vec!["sunday", "next sunday"]
+        );
+    }
+
+    #[test]
+    fn calendar_months() {
+        // 2024-01-31, 2024-02-01, 2024-02-29 and 2024-03-01
+        let commits = commits(&[
+            ("january 31", 1706659200),
+            ("february 1", 1706745600),
+            ("february 29", 1709164800),
+            ("march 1", 1709251200),
+        ]);
+        assert_eq!(
+            selected(&commits, FrameStep::Month),
+            vec!["january 31", "february 29", "march 1"]
+        );
+        assert_eq!(selected(&commits, FrameStep::Days(1)).len(), 4);
+    }
+}
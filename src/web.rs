@@ -0,0 +1,336 @@
+use crate::error::SyntaxError;
+use crate::refs::{Context, Extractor, Reference, ReferenceKind};
+use crate::structure::{join_path, parent_path};
+
+/// Finds the assets linked from HTML and CSS files: `<script src>`, `<link href>` and the
+/// `src` of other elements like images in HTML, and `@import` and `url(...)` in CSS and in
+/// `<style>` elements. Relative URLs are resolved against the folder of the file, URLs
+/// starting with `/` against the nearest folder above it that has the file. URLs with a
+/// scheme, like `https:` or `data:`, are left out.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WebExtractor;
+
+impl Extractor for WebExtractor {
+    fn language(&self) -> &str {
+        "web"
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        let extension = path.rsplit_once('.').map_or("", |(_, e)| e);
+        matches!(extension, "html" | "htm" | "css" | "scss" | "less")
+    }
+
+    fn extract(&self, path: &str, contents: &str) -> Result<Vec<Reference>, SyntaxError> {
+        let urls = if path.ends_with(".html") || path.ends_with(".htm") {
+            html_urls(contents)
+        } else {
+            css_urls(contents)
+        };
+        let folder = parent_path(path);
+        Ok(urls
+            .into_iter()
+            .map(|url| Reference::new(ReferenceKind::Include, url, folder))
+            .collect())
+    }
+
+    fn resolve(&self, _: &str, reference: &Reference, context: &Context) -> Option<String> {
+        let exists = &|p: &str| context.exists(p);
+        let url = reference.target.trim();
+        let scheme = url.split_once(':').map(|(scheme, _)| scheme);
+        if url.starts_with("//") || scheme.is_some_and(|s| !s.contains('/')) {
+            return None;
+        }
+        let url = url.split(['?', '#']).next().unwrap_or_default();
+        if url.is_empty() {
+            return None;
+        }
+        let absolute = match url.strip_prefix('/') {
+            Some(absolute) => absolute,
+            None => return find_file(&join_path(&reference.scope, url), exists),
+        };
+        // The root of the site is not known, so try every folder up from the file
+        let mut folder = Some(reference.scope.as_str());
+        while let Some(current) = folder {
+            if let Some(found) = find_file(&join_path(current, absolute), exists) {
+                return Some(found);
+            }
+            folder = (!current.is_empty()).then(|| parent_path(current));
+        }
+        None
+    }
+}
+
+/// Find the file a path refers to, also trying the Sass and Less imports without an
+/// extension and Sass partials starting with `_`
+fn find_file(path: &str, exists: &dyn Fn(&str) -> bool) -> Option<String> {
+    let folder = parent_path(path);
+    let name = path.rsplit('/').next().unwrap_or_default();
+    std::iter::once(path.to_string())
+        .chain(["scss", "less", "css"].map(|e| format!("{}.{}", path, e)))
+        .chain(["scss", "css"].map(|e| join_path(folder, &format!("_{}.{}", name, e))))
+        .find(|c| exists(c))
+}
+
+/// The URLs of the `src` attributes and the `href` attributes of `<link>` elements, and the
+/// ones in `<style>` elements
+fn html_urls(contents: &str) -> Vec<String> {
+    let mut urls = Vec::new();
+    let mut rest = contents;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        if let Some(comment) = rest.strip_prefix("!--") {
+            rest = comment.split_once("-->").map_or("", |(_, r)| r);
+            continue;
+        }
+        let name_end = rest
+            .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+            .unwrap_or(rest.len());
+        let name = rest[..name_end].to_ascii_lowercase();
+        let (attributes, after) = split_tag(&rest[name_end..]);
+        for (attribute, value) in attributes {
+            if attribute == "src" || (attribute == "href" && name == "link") {
+                urls.push(value.to_string());
+            }
+        }
+        rest = after;
+        if name == "style" {
+            let end = rest
+                .to_ascii_lowercase()
+                .find("</style")
+                .unwrap_or(rest.len());
+            urls.extend(css_urls(&rest[..end]));
+            rest = &rest[end..];
+        }
+    }
+    urls
+}
+
+/// Split the attributes of a tag (lower cased, with their values) from the text after it
+fn split_tag(tag: &str) -> (Vec<(String, &str)>, &str) {
+    let mut attributes = Vec::new();
+    let mut rest = tag;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+        if rest.is_empty() {
+            return (attributes, rest);
+        }
+        if let Some(after) = rest.strip_prefix('>') {
+            return (attributes, after);
+        }
+        let name_end = rest
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '>')
+            .unwrap_or(rest.len())
+            .max(1);
+        let name = rest[..name_end].to_ascii_lowercase();
+        rest = rest[name_end..].trim_start();
+        let value = match rest.strip_prefix('=') {
+            Some(value) => value.trim_start(),
+            None => continue,
+        };
+        let (value, after) = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split_once(quote).unwrap_or((&value[1..], "")),
+            _ => {
+                let end = value
+                    .find(|c: char| c.is_whitespace() || c == '>')
+                    .unwrap_or(value.len());
+                (&value[..end], &value[end..])
+            }
+        };
+        attributes.push((name, value));
+        rest = after;
+    }
+}
+
+/// The URLs of `@import` rules and `url(...)` values, leaving out comments
+fn css_urls(contents: &str) -> Vec<String> {
+    let mut text = String::with_capacity(contents.len());
+    let mut rest = contents;
+    while let Some((before, after)) = rest.split_once("/*") {
+        text.push_str(before);
+        rest = after.split_once("*/").map_or("", |(_, r)| r);
+    }
+    text.push_str(rest);
+
+    let mut urls = Vec::new();
+    let mut rest = text.as_str();
+    while let Some(start) = rest.find(['@', 'u', 'U']) {
+        let candidate = &rest[start..];
+        rest = &candidate[1..];
+        let value = if let Some(import) = candidate.strip_prefix("@import") {
+            // `@import "a.css"` or `@import url(a.css)`, the latter is found next
+            match import.trim_start().chars().next() {
+                Some(quote @ ('"' | '\'')) => import.trim_start()[1..].split_once(quote),
+                _ => None,
+            }
+        } else if candidate
+            .get(..4)
+            .is_some_and(|s| s.eq_ignore_ascii_case("url("))
+        {
+            let inner = candidate[4..].trim_start();
+            match inner.chars().next() {
+                Some(quote @ ('"' | '\'')) => inner[1..].split_once(quote),
+                _ => inner.split_once(')').map(|(url, r)| (url.trim_end(), r)),
+            }
+        } else {
+            None
+        };
+        if let Some((url, after)) = value {
+            urls.push(url.to_string());
+            rest = after;
+        }
+    }
+    urls
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(path: &str, url: &str, files: &[&str]) -> Option<String> {
+        let exists = |p: &str| files.contains(&p);
+        let reference = Reference::new(ReferenceKind::Include, url, parent_path(path));
+        WebExtractor.resolve(path, &reference, &Context::new(&exists, &|_| None))
+    }
+
+    #[test]
+    fn html() {
+        let source = concat!(
+            "<!DOCTYPE html>\n",
+            "<html>\n",
+            "<head>\n",
+            "  <LINK rel=stylesheet HREF=\"main.css\">\n",
+            "  <link rel='icon' href='icon.png'/>\n",
+            "  <script src=app.js defer></script>\n",
+            "  <!-- <script src=\"old.js\"></script> -->\n",
+            "  <style>\n",
+            "    @import 'theme.css';\n",
+            "    body { background: url(bg.png) }\n",
+            "    /* a { background: url(a.png) } */\n",
+            "  </STYLE>\n",
+            "</head>\n",
+            "<body>\n",
+            "  <a href=\"page.html\" title=\"src=x.js\">link</a>\n",
+            "  <img alt = \"a > b\" src = \"img/logo.svg\" hidden>\n",
+            "  <p>url(text.png) src=\"text.js\"</p>\n",
+            "</body>\n",
+            "</html>\n",
+        );
+        assert_eq!(
+            html_urls(source),
+            vec![
+                "main.css",
+                "icon.png",
+                "app.js",
+                "theme.css",
+                "bg.png",
+                "img/logo.svg"
+            ]
+        );
+    }
+
+    #[test]
+    fn tags() {
+        assert_eq!(
+            split_tag(" a=1 b = \"2 3\" c='>' d/>after"),
+            (
+                vec![
+                    ("a".to_string(), "1"),
+                    ("b".to_string(), "2 3"),
+                    ("c".to_string(), ">"),
+                ],
+                "after"
+            )
+        );
+        // An unterminated quote takes the rest of the text
+        assert_eq!(
+            split_tag(" SRC=\"a.js>"),
+            (vec![("src".to_string(), "a.js>")], "")
+        );
+    }
+
+    #[test]
+    fn css() {
+        let source = concat!(
+            "@import \"base.css\";\n",
+            "@import url(\"print.css\") print;\n",
+            "/* @import 'commented.css'; */\n",
+            ".a { background: URL( 'a.png' ) }\n",
+            ".b { background: url( b.png ) }\n",
+            ".c { filter: blur(2px); mask: url(#mask) }\n",
+            "@font-face { src: url(font.woff2?v=2#iefix) }\n",
+        );
+        assert_eq!(
+            css_urls(source),
+            vec![
+                "base.css",
+                "print.css",
+                "a.png",
+                "b.png",
+                "#mask",
+                "font.woff2?v=2#iefix"
+            ]
+        );
+    }
+
+    #[test]
+    fn urls() {
+        let files = ["site/css/main.css", "site/img/a.png", "site/font.woff2"];
+        let from = "site/css/main.css";
+        assert_eq!(
+            resolve(from, "../img/a.png", &files),
+            Some("site/img/a.png".to_string())
+        );
+        assert_eq!(
+            resolve(from, "../font.woff2?v=2#iefix", &files),
+            Some("site/font.woff2".to_string())
+        );
+        assert_eq!(resolve(from, "main.css", &files), Some(from.to_string()));
+        // URLs with a scheme, to other hosts and to fragments only are left out
+        for url in [
+            "https://x.org/a.png",
+            "//x.org/a.png",
+            "data:image/png;base64,AA",
+            "#mask",
+        ] {
+            assert_eq!(resolve(from, url, &files), None);
+        }
+        assert_eq!(resolve(from, "missing.png", &files), None);
+    }
+
+    #[test]
+    fn root_relative_urls() {
+        let files = ["site/index.html", "site/img/a.png", "img/b.png"];
+        // The nearest folder above the file with the file is the root
+        assert_eq!(
+            resolve("site/blog/post.html", "/img/a.png", &files),
+            Some("site/img/a.png".to_string())
+        );
+        assert_eq!(
+            resolve("site/blog/post.html", "/img/b.png", &files),
+            Some("img/b.png".to_string())
+        );
+        assert_eq!(resolve("site/index.html", "/img/c.png", &files), None);
+    }
+
+    #[test]
+    fn sass_and_less_imports() {
+        let files = [
+            "styles/_variables.scss",
+            "styles/mixins.less",
+            "styles/_reset.css",
+        ];
+        let from = "styles/main.scss";
+        assert_eq!(
+            resolve(from, "variables", &files),
+            Some("styles/_variables.scss".to_string())
+        );
+        assert_eq!(
+            resolve(from, "mixins", &files),
+            Some("styles/mixins.less".to_string())
+        );
+        assert_eq!(
+            resolve(from, "reset", &files),
+            Some("styles/_reset.css".to_string())
+        );
+    }
+}